@@ -1,15 +1,57 @@
-use crate::modeling::State;
-use jingle_sleigh::{RegisterManager, SpaceInfo, SpaceManager, VarNode};
+use crate::modeling::{ModelingContext, State};
+use crate::varnode::ResolvedVarnode;
+use crate::JingleError;
+use jingle_sleigh::{
+    PcodeOperation, RegisterManager, SpaceInfo, SpaceManager, UserOpManager, VarNode,
+};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
 use std::rc::Rc;
-use z3::Context;
+use z3::ast::{Ast, Bool, BV};
+use z3::{Context, Model, SatResult, Solver};
+
+/// A user-provided model of a `SLEIGH` user-defined operation. It is called in place of the
+/// default `CALLOTHER` modeling with the state to update, the operation's inputs (not including
+/// the userop index) and its output, if any.
+pub type UserOpHandler<'ctx> =
+    Rc<dyn Fn(&mut State<'ctx>, &[VarNode], Option<&VarNode>) -> Result<(), JingleError> + 'ctx>;
+
+/// A user-provided callback run after each p-code operation is modeled. It is given the operation
+/// and, if the operation has an output, the value the output holds afterward.
+pub type OpObserver<'ctx> = Rc<dyn Fn(&PcodeOperation, Option<&BV<'ctx>>) + 'ctx>;
+
+#[derive(Clone, Default)]
+struct OpObserverSlot<'ctx>(Option<OpObserver<'ctx>>);
+
+impl Debug for OpObserverSlot<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(<observer>)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct UserOpHandlers<'ctx>(HashMap<String, UserOpHandler<'ctx>>);
+
+impl Debug for UserOpHandlers<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct JingleContextInternal<'ctx> {
     pub z3: &'ctx Context,
     spaces: Vec<SpaceInfo>,
     default_code_space_index: usize,
+    unique_space_index: Option<usize>,
     registers: Vec<(VarNode, String)>,
+    userops: Vec<String>,
+    userop_handlers: UserOpHandlers<'ctx>,
+    op_observer: OpObserverSlot<'ctx>,
 }
 
 #[derive(Clone, Debug)]
@@ -23,19 +65,164 @@ impl<'ctx> Deref for JingleContext<'ctx> {
     }
 }
 impl<'ctx> JingleContext<'ctx> {
-    pub fn new<S: RegisterManager>(z3: &'ctx Context, r: &S) -> Self {
+    pub fn new<S: RegisterManager + UserOpManager>(z3: &'ctx Context, r: &S) -> Self {
         let spaces = r.get_all_space_info().to_vec();
         let default_code_space_index = r.get_code_space_idx();
+        let unique_space_index = r.get_unique_space_idx();
         Self(Rc::new(JingleContextInternal {
             z3,
             spaces,
             default_code_space_index,
+            unique_space_index,
             registers: r.get_registers(),
+            userops: r.get_userop_names().to_vec(),
+            userop_handlers: Default::default(),
+            op_observer: Default::default(),
         }))
     }
+
+    /// Register a handler modeling the user-defined operation `name`. `CALLOTHER` operations
+    /// targeting this userop will run the handler instead of the default modeling, which
+    /// writes an opaque, deterministic value to the output.
+    ///
+    /// Handlers are only visible to models built from the returned context, so they should be
+    /// registered before any modeling takes place.
+    pub fn with_userop_handler<F>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(&mut State<'ctx>, &[VarNode], Option<&VarNode>) -> Result<(), JingleError> + 'ctx,
+    {
+        Rc::make_mut(&mut self.0)
+            .userop_handlers
+            .0
+            .insert(name.to_string(), Rc::new(handler));
+        self
+    }
+
+    /// Get the handler registered for the user-defined operation `name`, if any
+    pub fn get_userop_handler(&self, name: &str) -> Option<&UserOpHandler<'ctx>> {
+        self.userop_handlers.0.get(name)
+    }
+
+    /// Register a callback to be run after every p-code operation modeled using the returned
+    /// context, e.g. to collect a symbolic trace. Replaces any previously registered observer.
+    ///
+    /// Like userop handlers, the observer is only visible to models built from the returned
+    /// context.
+    pub fn with_op_observer<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(&PcodeOperation, Option<&BV<'ctx>>) + 'ctx,
+    {
+        Rc::make_mut(&mut self.0).op_observer = OpObserverSlot(Some(Rc::new(observer)));
+        self
+    }
+
+    /// Get the registered operation observer, if any
+    pub fn get_op_observer(&self) -> Option<&OpObserver<'ctx>> {
+        self.op_observer.0.as_ref()
+    }
+
+    /// Produce a copy of this context for use with the z3 [Context] `z3`, e.g. to solve queries in
+    /// parallel. Models are moved over with their own `translate` methods, which take the
+    /// translated context so that they can share it.
+    ///
+    /// Registered userop handlers and op observers are bound to the original z3 context and are
+    /// not carried over.
+    pub fn translate<'dest>(&self, z3: &'dest Context) -> JingleContext<'dest> {
+        JingleContext(Rc::new(JingleContextInternal {
+            z3,
+            spaces: self.spaces.clone(),
+            default_code_space_index: self.default_code_space_index,
+            unique_space_index: self.unique_space_index,
+            registers: self.registers.clone(),
+            userops: self.userops.clone(),
+            userop_handlers: Default::default(),
+            op_observer: Default::default(),
+        }))
+    }
+
     pub fn fresh_state(&self) -> State<'ctx> {
         State::new(self)
     }
+
+    /// Like [fresh_state](Self::fresh_state), but names the array of each space
+    /// `{prefix}_{space_name}`, making SMT output comparing several states easier to read.
+    pub fn fresh_state_named(&self, prefix: &str) -> State<'ctx> {
+        State::new_named(self, prefix)
+    }
+
+    /// Returns a [Bool] assertion that, if `a` and `b` start from equal architectural states, then
+    /// they end in equal architectural states. Validity of this assertion (i.e. unsatisfiability of
+    /// its negation) proves the two are semantically equivalent.
+    pub fn assert_equivalent<A: ModelingContext<'ctx>, B: ModelingContext<'ctx>>(
+        &self,
+        a: &A,
+        b: &B,
+    ) -> Result<Bool<'ctx>, JingleError> {
+        let pre = a.get_original_state()._eq(b.get_original_state())?;
+        let post = Bool::and(
+            self.z3,
+            &[&a.upholds_postcondition(b)?, &b.upholds_postcondition(a)?],
+        );
+        Ok(pre.implies(&post))
+    }
+
+    /// Check whether `a` and `b` have the same effect on architectural state when started from
+    /// the same state. Returns the outputs (of either) whose final values can differ; an empty
+    /// result means the two are equivalent.
+    pub fn blocks_equivalent<A: ModelingContext<'ctx>, B: ModelingContext<'ctx>>(
+        &self,
+        a: &A,
+        b: &B,
+    ) -> Result<Vec<ResolvedVarnode<'ctx>>, JingleError> {
+        let mut outputs = a.get_outputs();
+        outputs.extend(b.get_outputs());
+        let mut terms = vec![];
+        for vn in outputs
+            .into_iter()
+            .filter(|v| a.should_varnode_constrain(v))
+        {
+            let ours = a.get_final_state().read_resolved(&vn)?;
+            let theirs = b.get_final_state().read_resolved(&vn)?;
+            terms.push((vn, ours._eq(&theirs).simplify()));
+        }
+        let eq_terms: Vec<&Bool> = terms.iter().map(|(_, t)| t).collect();
+        let solver = Solver::new(self.z3);
+        solver.assert(&a.get_original_state()._eq(b.get_original_state())?);
+        solver.assert(&Bool::and(self.z3, eq_terms.as_slice()).not());
+        let differing = self.check_and_extract(&solver, |model| {
+            terms
+                .into_iter()
+                .filter(|(_, t)| {
+                    model
+                        .eval(t, true)
+                        .and_then(|b| b.as_bool())
+                        .is_some_and(|b| !b)
+                })
+                .map(|(vn, _)| vn)
+                .collect::<Vec<_>>()
+        })?;
+        Ok(differing.unwrap_or_default())
+    }
+
+    /// Check the given solver and, if its assertions are satisfiable, pass the resulting model
+    /// to `f`. Returns `None` if the assertions are unsatisfiable.
+    pub fn check_and_extract<F, R>(
+        &self,
+        solver: &Solver<'ctx>,
+        f: F,
+    ) -> Result<Option<R>, JingleError>
+    where
+        F: FnOnce(&Model<'ctx>) -> R,
+    {
+        match solver.check() {
+            SatResult::Unsat => Ok(None),
+            SatResult::Unknown => Err(JingleError::SolverUnknown),
+            SatResult::Sat => {
+                let model = solver.get_model().ok_or(JingleError::SolverUnknown)?;
+                Ok(Some(f(&model)))
+            }
+        }
+    }
 }
 
 impl SpaceManager for JingleContext<'_> {
@@ -50,6 +237,16 @@ impl SpaceManager for JingleContext<'_> {
     fn get_code_space_idx(&self) -> usize {
         self.default_code_space_index
     }
+
+    fn get_unique_space_idx(&self) -> Option<usize> {
+        self.unique_space_index
+    }
+}
+
+impl UserOpManager for JingleContext<'_> {
+    fn get_userop_names(&self) -> &[String] {
+        self.userops.as_slice()
+    }
 }
 
 impl RegisterManager for JingleContext<'_> {
@@ -69,3 +266,162 @@ impl RegisterManager for JingleContext<'_> {
         self.registers.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::modeling::{ModeledBlock, ModeledInstruction, ModelingContext};
+    use crate::tests::SLEIGH_ARCH;
+    use crate::varnode::ResolvedVarnode;
+    use crate::JingleContext;
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{varnode, PcodeOperation, RegisterManager, SpaceManager};
+    use std::cell::RefCell;
+    use z3::ast::{Ast, BV};
+    use z3::{Config, Context, SatResult, Solver};
+
+    #[test]
+    fn test_fresh_state_named() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = jingle.fresh_state_named("pre");
+        for info in jingle.get_all_space_info() {
+            let array = state.get_space(info.index).unwrap();
+            let expected = format!("pre_{}", info.name);
+            assert!(format!("{:?}", array).contains(&expected));
+        }
+    }
+
+    #[test]
+    fn test_blocks_equivalent() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, 1; RET; MOV RAX, 2; RET
+        let img: [u8; 16] = [
+            0x48, 0xc7, 0xc0, 0x01, 0x00, 0x00, 0x00, 0xc3, 0x48, 0xc7, 0xc0, 0x02, 0x00, 0x00,
+            0x00, 0xc3,
+        ];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let a = ModeledBlock::read(&jingle, loaded.read(0, 2)).unwrap();
+        let b = ModeledBlock::read(&jingle, loaded.read(8, 2)).unwrap();
+        assert_eq!(
+            jingle.blocks_equivalent(&a, &a.fresh().unwrap()).unwrap(),
+            vec![]
+        );
+        let rax = loaded.get_register("RAX").unwrap();
+        assert_eq!(
+            jingle.blocks_equivalent(&a, &b).unwrap(),
+            vec![ResolvedVarnode::Direct(rax)]
+        );
+    }
+
+    #[test]
+    fn test_assert_equivalent() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // LEA RAX, [RAX + RAX]; LEA RAX, [RAX * 2]; LEA RAX, [RAX + 1]
+        let img: [u8; 16] = [
+            0x48, 0x8d, 0x04, 0x00, 0x48, 0x8d, 0x04, 0x45, 0x00, 0x00, 0x00, 0x00, 0x48, 0x8d,
+            0x40, 0x01,
+        ];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let add = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let mult = ModeledInstruction::new(loaded.instruction_at(4).unwrap(), &jingle).unwrap();
+        let inc = ModeledInstruction::new(loaded.instruction_at(12).unwrap(), &jingle).unwrap();
+        let solver = Solver::new(&z3);
+        let equiv = jingle.assert_equivalent(&add, &mult).unwrap();
+        assert_eq!(solver.check_assumptions(&[equiv.not()]), SatResult::Unsat);
+        let equiv = jingle.assert_equivalent(&add, &inc).unwrap();
+        assert_eq!(solver.check_assumptions(&[equiv.not()]), SatResult::Sat);
+    }
+
+    #[test]
+    fn test_check_and_extract() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = jingle.fresh_state();
+        let rax = state
+            .read_varnode(&sleigh.get_register("RAX").unwrap())
+            .unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(&rax._eq(&BV::from_u64(&z3, 0x1234, 64)));
+        let val = jingle
+            .check_and_extract(&solver, |model| {
+                model.eval(&rax, true).and_then(|v| v.as_u64())
+            })
+            .unwrap();
+        assert_eq!(val, Some(Some(0x1234)));
+        solver.assert(&rax._eq(&BV::from_u64(&z3, 0x5678, 64)));
+        let val = jingle.check_and_extract(&solver, |_| ()).unwrap();
+        assert_eq!(val, None);
+    }
+
+    #[test]
+    fn test_userop_handler() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // RDTSC
+        let img: [u8; 2] = [0x0f, 0x31];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded).with_userop_handler(
+            "rdtsc",
+            |state, _inputs, output| {
+                let val = BV::from_u64(&z3, 0x1122_3344_5566_7788, 64);
+                state.write_varnode(output.unwrap(), val)
+            },
+        );
+        let instr = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let final_state = instr.get_final_state();
+        let eax = final_state
+            .read_varnode(&loaded.get_register("EAX").unwrap())
+            .unwrap()
+            .simplify();
+        let edx = final_state
+            .read_varnode(&loaded.get_register("EDX").unwrap())
+            .unwrap()
+            .simplify();
+        assert_eq!(eax.as_u64(), Some(0x5566_7788));
+        assert_eq!(edx.as_u64(), Some(0x1122_3344));
+    }
+
+    #[test]
+    fn test_op_observer() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let trace = RefCell::new(vec![]);
+        let jingle = JingleContext::new(&z3, &sleigh).with_op_observer(|_op, output| {
+            trace
+                .borrow_mut()
+                .push(output.and_then(|o| o.simplify().as_u64()));
+        });
+        let rax = varnode!(&sleigh, "register"[0]:8).unwrap();
+        let ops = vec![
+            PcodeOperation::Copy {
+                input: varnode!(&sleigh, #5:8).unwrap(),
+                output: rax.clone(),
+            },
+            PcodeOperation::IntAdd {
+                input0: rax.clone(),
+                input1: varnode!(&sleigh, #3:8).unwrap(),
+                output: rax.clone(),
+            },
+        ];
+        ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        assert_eq!(*trace.borrow(), vec![Some(5), Some(8)]);
+    }
+}
@@ -1,4 +1,4 @@
-use jingle_sleigh::{JingleSleighError, PcodeOperation};
+use jingle_sleigh::{GeneralizedVarNode, JingleSleighError, PcodeOperation};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -11,20 +11,32 @@ pub enum JingleError {
     IntraInstructionControlFlow,
     #[error("A z3 array selection operation returned something other than a bitvector")]
     UnexpectedArraySort,
-    #[error("Something referenced a space that isn't declared")]
-    UnmodeledSpace,
+    #[error("Something referenced space {0}, which isn't declared")]
+    UnmodeledSpace(usize),
     #[error("Tried to create a block containing zero instructions")]
     EmptyBlock,
+    #[error("Tried to fuse two blocks where the first cannot exit to the start of the second")]
+    DisconnectedBlocks,
     #[error("Something tried to access a 0-sized varnode")]
     ZeroSizedVarnode,
-    #[error("Cannot write values into constant space.")]
-    ConstantWrite,
+    #[error("Cannot write values into constant space (attempted write to {0:?}).")]
+    ConstantWrite(GeneralizedVarNode),
     #[error("Attempt to read an indirect value from the constant space. While this can be modeled, it's almost definitely unintended.")]
     IndirectConstantRead,
     #[error("Attempted to perform a write of a bitvector to a VarNode with leftover space. This is a sleigh bug.")]
     MismatchedWordSize,
+    #[error("A bit field of {size} bits at bit {position} does not fit in a {width}-bit value")]
+    BitFieldOutOfRange {
+        position: u64,
+        size: u64,
+        width: u32,
+    },
     #[error("Attempted to perform a write to a space using the wrong size of address. This is a sleigh bug.")]
     MismatchedAddressSize,
-    #[error("Jingle does not yet model this instruction")]
+    #[error("Tried to assume a value is one of an empty set of candidates")]
+    NoCandidates,
+    #[error("The solver could not determine the satisfiability of a query")]
+    SolverUnknown,
+    #[error("Jingle does not yet model the {} operation", .0.opcode())]
     UnmodeledInstruction(Box<PcodeOperation>),
 }
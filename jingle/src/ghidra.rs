@@ -0,0 +1,62 @@
+use jingle_sleigh::context::SleighContextBuilder;
+use jingle_sleigh::JingleSleighError;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// The environment variable consulted by [GhidraInstallation::from_env]. This is the same
+/// variable used by Ghidra's own launch scripts.
+pub const GHIDRA_INSTALL_DIR: &str = "GHIDRA_INSTALL_DIR";
+
+/// The location of a Ghidra installation, from which `SLEIGH` language definitions are loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhidraInstallation {
+    path: PathBuf,
+}
+
+impl GhidraInstallation {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Locate a Ghidra installation using the `GHIDRA_INSTALL_DIR` environment variable, if set.
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_value(std::env::var_os(GHIDRA_INSTALL_DIR))
+    }
+
+    /// Interpret the value of `GHIDRA_INSTALL_DIR`, if set, as an installation path
+    fn from_env_value(value: Option<OsString>) -> Option<Self> {
+        value.map(Self::from_path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the language definitions shipped with this installation.
+    pub fn sleigh_builder(&self) -> Result<SleighContextBuilder, JingleSleighError> {
+        SleighContextBuilder::load_ghidra_installation(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ghidra::{GhidraInstallation, GHIDRA_INSTALL_DIR};
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    #[test]
+    fn test_from_env() {
+        let install =
+            GhidraInstallation::from_env_value(Some(OsString::from("/opt/ghidra_test"))).unwrap();
+        assert_eq!(install.path(), Path::new("/opt/ghidra_test"));
+        assert_eq!(install, GhidraInstallation::from_path("/opt/ghidra_test"));
+        assert_eq!(GhidraInstallation::from_env_value(None), None);
+        // from_env reads whatever the environment holds, without modifying it
+        assert_eq!(
+            GhidraInstallation::from_env(),
+            GhidraInstallation::from_env_value(std::env::var_os(GHIDRA_INSTALL_DIR))
+        );
+    }
+}
@@ -1,13 +1,15 @@
 mod context;
 mod error;
+mod ghidra;
 pub mod modeling;
 mod translator;
 pub mod varnode;
 
 pub use jingle_sleigh as sleigh;
 
-pub use context::JingleContext;
+pub use context::{JingleContext, OpObserver, UserOpHandler};
 pub use error::JingleError;
+pub use ghidra::{GhidraInstallation, GHIDRA_INSTALL_DIR};
 pub use translator::SleighTranslator;
 
 #[cfg(test)]
@@ -1,11 +1,11 @@
 use anyhow::Context;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use hex::decode;
 use jingle::modeling::{ModeledBlock, ModelingContext};
-use jingle::JingleContext;
+use jingle::{GhidraInstallation, JingleContext};
 use jingle_sleigh::context::loaded::LoadedSleighContext;
 use jingle_sleigh::context::SleighContextBuilder;
-use jingle_sleigh::{Disassembly, Instruction, JingleSleighError, PcodeOperation, VarNode};
+use jingle_sleigh::{Instruction, JingleSleighError, SpaceManager};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use z3::ast::Ast;
@@ -18,7 +18,7 @@ struct JingleConfig {
 
 impl JingleConfig {
     pub fn sleigh_builder(&self) -> Result<SleighContextBuilder, JingleSleighError> {
-        SleighContextBuilder::load_ghidra_installation(&self.ghidra_path)
+        GhidraInstallation::from_path(&self.ghidra_path).sleigh_builder()
     }
 }
 
@@ -56,22 +56,45 @@ struct JingleParams {
     pub ghidra_path: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// The JSON representation of a disassembled instruction
+#[derive(Debug, Serialize)]
+struct DisassemblyRecord<'a> {
+    address: u64,
+    mnemonic: &'a str,
+    args: &'a str,
+    length: usize,
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Adds files to myapp
     Disassemble {
         architecture: String,
         hex_bytes: String,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
     Lift {
         architecture: String,
         hex_bytes: String,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
     },
     Model {
         architecture: String,
         hex_bytes: String,
     },
-    Architectures,
+    /// Lists the available architectures, optionally only those whose id contains `filter`
+    /// (ignoring case)
+    Architectures { filter: Option<String> },
+    /// Prints the table of address spaces used by an architecture
+    Info { architecture: String },
 }
 
 fn main() -> anyhow::Result<()> {
@@ -82,19 +105,22 @@ fn main() -> anyhow::Result<()> {
         Commands::Disassemble {
             architecture,
             hex_bytes,
-        } => disassemble(&config, architecture, hex_bytes),
+            format,
+        } => disassemble(&config, architecture, hex_bytes, format),
         Commands::Lift {
             architecture,
             hex_bytes,
-        } => lift(&config, architecture, hex_bytes),
+            format,
+        } => lift(&config, architecture, hex_bytes, format),
         Commands::Model {
             architecture,
             hex_bytes,
         } => model(&config, architecture, hex_bytes),
-        Commands::Architectures => {
-            list_architectures(&config);
+        Commands::Architectures { filter } => {
+            list_architectures(&config, filter);
             Ok(())
         }
+        Commands::Info { architecture } => info(&config, architecture),
     }
 }
 
@@ -108,13 +134,44 @@ fn update_config(params: &JingleParams) {
     }
 }
 
-fn list_architectures(config: &JingleConfig) {
+fn list_architectures(config: &JingleConfig, filter: Option<String>) {
     let sleigh = config.sleigh_builder().unwrap();
-    for language_id in sleigh.get_language_ids() {
+    let language_ids = match filter {
+        Some(filter) => sleigh.find_languages(&filter),
+        None => sleigh.get_language_ids(),
+    };
+    for language_id in language_ids {
         println!("{}", language_id)
     }
 }
 
+fn info(config: &JingleConfig, architecture: String) -> anyhow::Result<()> {
+    let sleigh = config
+        .sleigh_builder()?
+        .build(&architecture)
+        .context("Unable to build the selected architecture.")?;
+    print!("{}", space_table(&sleigh));
+    Ok(())
+}
+
+fn space_table<T: SpaceManager>(ctx: &T) -> String {
+    let mut table = format!(
+        "{:<6}{:<16}{:<20}{:<12}{}\n",
+        "index", "name", "type", "index size", "word size"
+    );
+    for space in ctx.get_all_space_info() {
+        table.push_str(&format!(
+            "{:<6}{:<16}{:<20}{:<12}{}\n",
+            space.index,
+            space.name,
+            format!("{:?}", space._type),
+            space.index_size_bytes,
+            space.word_size_bytes
+        ));
+    }
+    table
+}
+
 fn get_instructions(
     config: &JingleConfig,
     architecture: String,
@@ -135,13 +192,11 @@ fn get_instructions(
     let sleigh = sleigh.initialize_with_image(img)?;
     let mut instrs = vec![];
     while offset < max_len {
-        if let Some(instruction) = sleigh.instruction_at(offset as u64) {
-            offset += instruction.length;
-            instrs.push(instruction);
-        }
-        if sleigh.instruction_at(offset as u64).is_none() {
-            break;
-        }
+        let instruction = sleigh
+            .try_instruction_at(offset as u64)
+            .with_context(|| format!("Unable to disassemble the instruction at {:#x}", offset))?;
+        offset += instruction.length;
+        instrs.push(instruction);
     }
     Ok((sleigh, instrs))
 }
@@ -150,20 +205,50 @@ fn disassemble(
     config: &JingleConfig,
     architecture: String,
     hex_bytes: String,
+    format: OutputFormat,
 ) -> anyhow::Result<()> {
-    for instr in get_instructions(config, architecture, hex_bytes)?.1 {
-        println!("{}", instr.disassembly)
+    let instrs = get_instructions(config, architecture, hex_bytes)?.1;
+    match format {
+        OutputFormat::Text => {
+            for instr in instrs {
+                println!("{}", instr.disassembly)
+            }
+        }
+        OutputFormat::Json => println!("{}", disassembly_json(&instrs)?),
     }
     Ok(())
 }
 
-fn lift(config: &JingleConfig, architecture: String, hex_bytes: String) -> anyhow::Result<()> {
+fn disassembly_json(instrs: &[Instruction]) -> serde_json::Result<String> {
+    let records: Vec<DisassemblyRecord> = instrs
+        .iter()
+        .map(|instr| DisassemblyRecord {
+            address: instr.address,
+            mnemonic: &instr.disassembly.mnemonic,
+            args: &instr.disassembly.args,
+            length: instr.length,
+        })
+        .collect();
+    serde_json::to_string_pretty(&records)
+}
+
+fn lift(
+    config: &JingleConfig,
+    architecture: String,
+    hex_bytes: String,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
     let (sleigh, instrs) = get_instructions(config, architecture, hex_bytes)?;
-    for instr in instrs {
-        for x in instr.ops {
-            let x_disp = x.display(&sleigh)?;
-            println!("{}", x_disp)
+    match format {
+        OutputFormat::Text => {
+            for instr in instrs {
+                for x in instr.ops {
+                    let x_disp = x.display(&sleigh)?;
+                    println!("{}", x_disp)
+                }
+            }
         }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&instrs)?),
     }
     Ok(())
 }
@@ -171,27 +256,7 @@ fn lift(config: &JingleConfig, architecture: String, hex_bytes: String) -> anyho
 fn model(config: &JingleConfig, architecture: String, hex_bytes: String) -> anyhow::Result<()> {
     let z3 = Z3Context::new(&Config::new());
     let solver = Solver::new(&z3);
-    let (sleigh, mut instrs) = get_instructions(config, architecture, hex_bytes)?;
-    // todo: this is a disgusting hack to let us read a modeled block without requiring the user
-    // to enter a block-terminating instruction. Everything with reading blocks needs to be reworked
-    // at some point. For now, this lets me not break anything else relying on this behavior while
-    // still getting this to work.
-    instrs.push(Instruction {
-        address: 0,
-        disassembly: Disassembly {
-            args: "".to_string(),
-            mnemonic: "".to_string(),
-        },
-        ops: vec![PcodeOperation::Branch {
-            input: VarNode {
-                space_index: 1,
-                offset: 0,
-                size: 1,
-            },
-        }],
-        length: 1,
-    });
-
+    let (sleigh, instrs) = get_instructions(config, architecture, hex_bytes)?;
     let jingle_ctx = JingleContext::new(&z3, &sleigh);
     let block = ModeledBlock::read(&jingle_ctx, instrs.into_iter())?;
     let final_state = jingle_ctx.fresh_state();
@@ -199,3 +264,55 @@ fn model(config: &JingleConfig, architecture: String, hex_bytes: String) -> anyh
     println!("{}", solver.to_smt2());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{disassembly_json, space_table};
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{Instruction, SpaceManager};
+
+    #[test]
+    fn test_space_table() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build("x86:LE:64:default").unwrap();
+        let table = space_table(&sleigh);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("index"));
+        assert_eq!(lines.len(), sleigh.get_all_space_info().len() + 1);
+        for (name, ty) in [
+            ("const", "IPTR_CONSTANT"),
+            ("unique", "IPTR_INTERNAL"),
+            ("ram", "IPTR_PROCESSOR"),
+            ("register", "IPTR_PROCESSOR"),
+        ] {
+            assert!(lines.iter().any(|l| {
+                let cols: Vec<&str> = l.split_whitespace().collect();
+                cols[1] == name && cols[2] == ty
+            }));
+        }
+    }
+
+    #[test]
+    fn test_json_output() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build("x86:LE:64:default").unwrap();
+        // PUSH RBP; RET
+        let img: [u8; 2] = [0x55, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let instrs: Vec<Instruction> = loaded.read(0, 2).collect();
+
+        let disassembly: serde_json::Value =
+            serde_json::from_str(&disassembly_json(&instrs).unwrap()).unwrap();
+        let records = disassembly.as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["address"], 0);
+        assert_eq!(records[0]["mnemonic"], "PUSH");
+        assert_eq!(records[1]["length"], 1);
+
+        let lifted = serde_json::to_string_pretty(&instrs).unwrap();
+        let parsed: Vec<Instruction> = serde_json::from_str(&lifted).unwrap();
+        assert_eq!(parsed, instrs);
+    }
+}
@@ -1,16 +1,28 @@
 use crate::error::JingleError;
-use crate::error::JingleError::DisassemblyLengthBound;
-use crate::modeling::branch::BranchConstraint;
+use crate::modeling::branch::{BlockEndBehavior, BranchConstraint};
 use crate::modeling::state::State;
-use crate::modeling::{ModelingContext, TranslationContext};
+use crate::modeling::{branch_agreement, ModelingContext, TranslationContext};
 use crate::varnode::ResolvedVarnode;
 use crate::JingleContext;
 use crate::JingleError::EmptyBlock;
 use jingle_sleigh::Instruction;
 use jingle_sleigh::PcodeOperation;
-use jingle_sleigh::{SpaceInfo, SpaceManager};
+use jingle_sleigh::{GeneralizedVarNode, SpaceInfo, SpaceManager};
 use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
+use z3::ast::{Ast, Bool, Dynamic, BV};
+use z3::{SatResult, Solver};
+
+/// A rough measure of how expensive a [ModeledBlock] is to reason about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockComplexity {
+    /// The number of p-code operations modeled in the block
+    pub ops: usize,
+    /// The indices of the spaces read or written by the block
+    pub spaces: HashSet<usize>,
+    /// The number of distinct z3 terms making up the final state of the block
+    pub terms: usize,
+}
 
 /// A `jingle` model of a basic block
 #[derive(Debug, Clone)]
@@ -22,6 +34,9 @@ pub struct ModeledBlock<'ctx> {
     branch_constraint: BranchConstraint,
     inputs: HashSet<ResolvedVarnode<'ctx>>,
     outputs: HashSet<ResolvedVarnode<'ctx>>,
+    /// Conditions under which the path modeled by this block is feasible, e.g. that each
+    /// block fused by [concat](Self::concat) actually exits to the next one
+    path_constraints: Vec<Bool<'ctx>>,
 }
 
 impl Display for ModeledBlock<'_> {
@@ -46,6 +61,7 @@ impl<'ctx, T: ModelingContext<'ctx>> TryFrom<&'ctx [T]> for ModeledBlock<'ctx> {
             original_state,
             inputs: Default::default(),
             outputs: Default::default(),
+            path_constraints: Default::default(),
             branch_constraint: BranchConstraint::with_same_final_branch(
                 vec.last().ok_or(EmptyBlock)?.get_branch_constraint(),
             ),
@@ -61,6 +77,9 @@ impl<'ctx, T: ModelingContext<'ctx>> TryFrom<&'ctx [T]> for ModeledBlock<'ctx> {
 }
 
 impl<'ctx> ModeledBlock<'ctx> {
+    /// Model the instructions yielded by `instr_iter`, up to and including the first one that
+    /// terminates a basic block. If the iterator runs out before a terminator is seen, the block
+    /// is modeled as falling through to the address after the last instruction.
     pub fn read<T: Iterator<Item = Instruction>>(
         jingle: &JingleContext<'ctx>,
         instr_iter: T,
@@ -68,29 +87,21 @@ impl<'ctx> ModeledBlock<'ctx> {
         let original_state = State::new(jingle);
         let state = original_state.clone();
 
-        let mut block_terminated = false;
         let mut ops = Vec::new();
         let mut instructions = Vec::new();
-        // The block_terminated check ensures that this function will only return successfully
-        // in cases where this has been initialized with an actual value.
-        let mut naive_fallthrough_address: u64 = 0;
         for instr in instr_iter {
             ops.extend_from_slice(&instr.ops);
-            if instr.terminates_basic_block() {
-                block_terminated = true;
-                naive_fallthrough_address = instr.next_addr();
-            }
+            let block_terminated = instr.terminates_basic_block();
             instructions.push(instr);
             if block_terminated {
                 break;
             }
         }
-        if !block_terminated {
-            return Err(DisassemblyLengthBound);
-        }
-        let vn = state.get_default_code_space_info().make_varnode(
+        let naive_fallthrough_address = instructions.last().ok_or(EmptyBlock)?.next_addr();
+        let code_space = state.get_default_code_space_info()?;
+        let vn = code_space.make_varnode(
             naive_fallthrough_address,
-            state.get_default_code_space_info().index_size_bytes as usize,
+            code_space.index_size_bytes as usize,
         );
 
         let mut model = Self {
@@ -101,6 +112,7 @@ impl<'ctx> ModeledBlock<'ctx> {
             branch_constraint: BranchConstraint::new(&vn),
             inputs: Default::default(),
             outputs: Default::default(),
+            path_constraints: Default::default(),
         };
         for op in ops {
             model.model_pcode_op(&op)?
@@ -108,10 +120,39 @@ impl<'ctx> ModeledBlock<'ctx> {
         Ok(model)
     }
 
+    /// Move this block over to the z3 context of `jingle`, which should have been produced by
+    /// [JingleContext::translate] on the context of this block.
+    pub fn translate<'dest>(&self, jingle: &JingleContext<'dest>) -> ModeledBlock<'dest> {
+        ModeledBlock {
+            jingle: jingle.clone(),
+            instructions: self.instructions.clone(),
+            state: self.state.translate(jingle),
+            original_state: self.original_state.translate(jingle),
+            branch_constraint: self.branch_constraint.clone(),
+            inputs: self.inputs.iter().map(|i| i.translate(jingle.z3)).collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|o| o.translate(jingle.z3))
+                .collect(),
+            path_constraints: self
+                .path_constraints
+                .iter()
+                .map(|c| c.translate(jingle.z3))
+                .collect(),
+        }
+    }
+
     pub fn fresh(&self) -> Result<Self, JingleError> {
         ModeledBlock::read(&self.jingle, self.instructions.clone().into_iter())
     }
 
+    /// The conditions under which the path modeled by this block is feasible. These are empty
+    /// for a block read directly from an image; see [concat](Self::concat).
+    pub fn path_constraints(&self) -> &[Bool<'ctx>] {
+        &self.path_constraints
+    }
+
     pub fn get_first_address(&self) -> u64 {
         self.instructions[0].address
     }
@@ -120,6 +161,201 @@ impl<'ctx> ModeledBlock<'ctx> {
         let i = self.instructions.last().unwrap();
         i.address + i.length as u64
     }
+
+    /// List the constant values (and their sizes in bytes) referenced by the operations of this
+    /// block, in order of first appearance. Useful for surfacing immediates like magic numbers
+    /// or syscall numbers.
+    pub fn constants(&self) -> Vec<(u64, usize)> {
+        let mut constants = vec![];
+        for op in self.get_ops() {
+            for input in op.inputs() {
+                if let GeneralizedVarNode::Direct(vn) = input {
+                    if self.is_const_space(vn.space_index)
+                        && !constants.contains(&(vn.offset, vn.size))
+                    {
+                        constants.push((vn.offset, vn.size));
+                    }
+                }
+            }
+        }
+        constants
+    }
+
+    /// Fuse `other` onto the end of this block, producing a single model of the path through
+    /// both. The final state of `self` becomes the state that `other`'s operations are applied
+    /// to, with the `unique` space reset in between. The resulting block takes its end-of-block
+    /// branching behavior from `other`.
+    ///
+    /// `self` must be able to exit to the first address of `other`. If it provably cannot, this
+    /// returns [JingleError::DisconnectedBlocks]. Otherwise, the condition under which it does
+    /// (e.g. the value of a conditional branch's flag) is recorded in the
+    /// [path constraints](Self::path_constraints) of the result, which must be asserted for the
+    /// fused model to describe a feasible path.
+    pub fn concat(mut self, other: ModeledBlock<'ctx>) -> Result<Self, JingleError> {
+        let reaches_other = self
+            .branch_constraint
+            .satisfies_target(&self, other.get_address())?
+            .simplify();
+        if reaches_other.as_bool() == Some(false) {
+            return Err(JingleError::DisconnectedBlocks);
+        }
+        if reaches_other.as_bool() != Some(true) {
+            self.path_constraints.push(reaches_other);
+        }
+        self.path_constraints
+            .extend(other.path_constraints.iter().cloned());
+        self.state.reset_internal_spaces();
+        self.branch_constraint = BranchConstraint::with_same_final_branch(&other.branch_constraint);
+        for op in other.get_ops() {
+            self.model_pcode_op(op)?;
+        }
+        self.instructions.extend(other.instructions);
+        Ok(self)
+    }
+
+    /// Enumerate up to `max` concrete addresses this block may branch to, subject to the
+    /// assertions already present in `solver`. Useful for recovering the targets of
+    /// computed jumps (e.g. jump tables).
+    ///
+    /// A block without any branch yields no targets, and a block ending in a direct
+    /// unconditional branch yields exactly that branch's destination. Otherwise, the
+    /// solver is repeatedly queried for a satisfying destination, blocking each one found.
+    /// The solver's assertion stack is left as it was found.
+    pub fn possible_targets(
+        &self,
+        solver: &Solver<'ctx>,
+        max: usize,
+    ) -> Result<Vec<u64>, JingleError> {
+        if !self.branch_constraint.has_branch() {
+            return Ok(vec![]);
+        }
+        if let BlockEndBehavior::UnconditionalBranch(GeneralizedVarNode::Direct(d)) =
+            &self.branch_constraint.last
+        {
+            if self.branch_constraint.conditional_branches.is_empty() {
+                return Ok(vec![d.offset].into_iter().take(max).collect());
+            }
+        }
+        let dest = self.branch_constraint.build_bv(self)?;
+        let mut targets = vec![];
+        solver.push();
+        while targets.len() < max && solver.check() == SatResult::Sat {
+            let target = solver
+                .get_model()
+                .and_then(|m| m.eval(&dest, true))
+                .and_then(|v| v.as_u64());
+            match target {
+                Some(target) => {
+                    solver.assert(
+                        &dest
+                            ._eq(&BV::from_u64(self.jingle.z3, target, dest.get_size()))
+                            .not(),
+                    );
+                    targets.push(target);
+                }
+                None => break,
+            }
+        }
+        solver.pop(1);
+        Ok(targets)
+    }
+
+    /// Report the size of this block's model. Useful for finding blocks that are pathologically
+    /// slow to solve.
+    pub fn complexity(&self) -> BlockComplexity {
+        let spaces = self
+            .inputs
+            .iter()
+            .chain(self.outputs.iter())
+            .map(|vn| match vn {
+                ResolvedVarnode::Direct(d) => d.space_index,
+                ResolvedVarnode::Indirect(i) => i.pointer_space_idx,
+            })
+            .collect();
+        let mut seen = HashSet::new();
+        let mut worklist: Vec<Dynamic> = self
+            .state
+            .spaces()
+            .map(|(_, array)| Dynamic::from_ast(array))
+            .collect();
+        while let Some(term) = worklist.pop() {
+            if seen.insert(term.clone()) {
+                worklist.extend(term.children());
+            }
+        }
+        BlockComplexity {
+            ops: self.get_ops().len(),
+            spaces,
+            terms: seen.len(),
+        }
+    }
+
+    /// Returns a [Bool] asserting that this block and `other` transfer control to the same
+    /// destination. Conditional branches of both blocks are accounted for, so the assertion is
+    /// only satisfiable under the condition values for which the destinations coincide. Unlike
+    /// [branch_comparison](ModelingContext::branch_comparison), this is produced even when this
+    /// block has no explicit branch.
+    pub fn branch_agrees_with<T: ModelingContext<'ctx>>(
+        &self,
+        other: &T,
+    ) -> Result<Bool<'ctx>, JingleError> {
+        branch_agreement(self, other)
+    }
+
+    /// Check whether any indirect write performed by this block could target `addr` in the space
+    /// with index `space`, subject to the assertions already present in `solver`.
+    pub fn can_write_to(
+        &self,
+        solver: &Solver<'ctx>,
+        space: usize,
+        addr: u64,
+    ) -> Result<bool, JingleError> {
+        for output in &self.outputs {
+            if let ResolvedVarnode::Indirect(i) = output {
+                if i.pointer_space_idx != space {
+                    continue;
+                }
+                let target = BV::from_u64(self.jingle.z3, addr, i.pointer.get_size());
+                match solver.check_assumptions(&[i.pointer._eq(&target)]) {
+                    SatResult::Sat => return Ok(true),
+                    SatResult::Unsat => {}
+                    SatResult::Unknown => return Err(JingleError::SolverUnknown),
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Run `f` against `solver` inside a fresh assertion scope in which this block's
+    /// [path constraints](Self::path_constraints) are asserted, so that queries made by `f` only
+    /// consider executions that actually follow this block's path. The scope, along with
+    /// anything `f` asserts in it, is popped once `f` returns or unwinds.
+    pub fn with_assertions<F, R>(&self, solver: &Solver<'ctx>, f: F) -> R
+    where
+        F: FnOnce(&Solver<'ctx>, &Self) -> R,
+    {
+        let scope = AssertionScope::new(solver);
+        for constraint in &self.path_constraints {
+            scope.0.assert(constraint);
+        }
+        f(scope.0, self)
+    }
+}
+
+/// Pops the [Solver] scope it pushed when dropped, so that the scope is not leaked on a panic
+struct AssertionScope<'a, 'ctx>(&'a Solver<'ctx>);
+
+impl<'a, 'ctx> AssertionScope<'a, 'ctx> {
+    fn new(solver: &'a Solver<'ctx>) -> Self {
+        solver.push();
+        Self(solver)
+    }
+}
+
+impl Drop for AssertionScope<'_, '_> {
+    fn drop(&mut self) {
+        self.0.pop(1);
+    }
 }
 
 impl SpaceManager for ModeledBlock<'_> {
@@ -191,3 +427,338 @@ impl<'ctx> TranslationContext<'ctx> for ModeledBlock<'ctx> {
         &mut self.branch_constraint
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::modeling::{BlockEndBehavior, ModeledBlock, ModelingContext};
+    use crate::tests::SLEIGH_ARCH;
+    use crate::{JingleContext, JingleError};
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{RegisterManager, SpaceManager};
+    use std::collections::HashSet;
+    use std::panic::AssertUnwindSafe;
+    use z3::ast::{Ast, Bool, BV};
+    use z3::{Config, Context, SatResult, Solver};
+
+    #[test]
+    fn test_delay_slot() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build("MIPS:BE:32:default").unwrap();
+        // b 0x10; addiu v0, zero, 1 (delay slot)
+        let img: [u8; 8] = [0x10, 0x00, 0x00, 0x03, 0x24, 0x02, 0x00, 0x01];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 2)).unwrap();
+        assert_eq!(block.instructions.len(), 1);
+        assert_eq!(block.instructions[0].length, 8);
+        let v0 = loaded.get_register("v0").unwrap();
+        let val = block
+            .get_final_state()
+            .read_varnode(&v0)
+            .unwrap()
+            .simplify();
+        assert_eq!(val.as_u64(), Some(1));
+        let solver = Solver::new(&z3);
+        let target = block
+            .get_branch_constraint()
+            .satisfies_target(&block, 0x10)
+            .unwrap();
+        assert_eq!(solver.check_assumptions(&[target]), SatResult::Sat);
+    }
+
+    #[test]
+    fn test_read_unterminated() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, 1; NOP
+        let img: [u8; 8] = [0x48, 0xc7, 0xc0, 0x01, 0x00, 0x00, 0x00, 0x90];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 2)).unwrap();
+        assert_eq!(block.instructions.len(), 2);
+        let branch = block.get_branch_constraint();
+        assert!(!branch.has_branch());
+        match &branch.last {
+            BlockEndBehavior::Fallthrough(vn) => assert_eq!(vn.offset, 8),
+            _ => panic!("expected the block to fall through"),
+        }
+        assert!(ModeledBlock::read(&jingle, std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn test_translate() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, 1; RET
+        let img: [u8; 8] = [0x48, 0xc7, 0xc0, 0x01, 0x00, 0x00, 0x00, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 2)).unwrap();
+
+        let other_z3 = Context::new(&Config::new());
+        let other_jingle = jingle.translate(&other_z3);
+        let translated = block.translate(&other_jingle);
+        assert_eq!(translated.get_outputs().len(), block.get_outputs().len());
+        let rax = loaded.get_register("RAX").unwrap();
+        let val = translated.get_final_state().read_varnode(&rax).unwrap();
+        let solver = Solver::new(&other_z3);
+        solver.assert(&val._eq(&BV::from_u64(&other_z3, 1, 64)).not());
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+
+    #[test]
+    fn test_possible_targets() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // JMP RAX
+        let img: [u8; 2] = [0xff, 0xe0];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let solver = Solver::new(&z3);
+        let rax = block
+            .get_original_state()
+            .read_varnode(&loaded.get_register("RAX").unwrap())
+            .unwrap();
+        solver.assert(&rax.bvult(&BV::from_u64(&z3, 3, 64)));
+        let mut targets = block.possible_targets(&solver, 10).unwrap();
+        targets.sort();
+        assert_eq!(targets, vec![0, 1, 2]);
+        assert_eq!(block.possible_targets(&solver, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_with_assertions() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // ADD RAX, 1
+        let img: [u8; 4] = [0x48, 0x83, 0xc0, 0x01];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let rax = loaded.get_register("RAX").unwrap();
+        let solver = Solver::new(&z3);
+        let output = block.with_assertions(&solver, |solver, block| {
+            let input = block.get_original_state().read_varnode(&rax).unwrap();
+            let output = block.get_final_state().read_varnode(&rax).unwrap();
+            solver.assert(&input._eq(&BV::from_u64(&z3, 4, 64)));
+            assert_eq!(solver.check(), SatResult::Sat);
+            let model = solver.get_model().unwrap();
+            model.eval(&output, true).and_then(|v| v.as_u64())
+        });
+        assert_eq!(output, Some(5));
+        let input = block.with_assertions(&solver, |solver, block| {
+            let input = block.get_original_state().read_varnode(&rax).unwrap();
+            let output = block.get_final_state().read_varnode(&rax).unwrap();
+            solver.assert(&output._eq(&BV::from_u64(&z3, 0, 64)));
+            assert_eq!(solver.check(), SatResult::Sat);
+            let model = solver.get_model().unwrap();
+            model.eval(&input, true).and_then(|v| v.as_u64())
+        });
+        assert_eq!(input, Some(u64::MAX));
+        assert!(solver.get_assertions().is_empty());
+        let panicked = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            block.with_assertions(&solver, |solver, _| {
+                solver.assert(&Bool::from_bool(&z3, false));
+                panic!("query failed");
+            })
+        }));
+        assert!(panicked.is_err());
+        assert!(solver.get_assertions().is_empty());
+    }
+
+    #[test]
+    fn test_with_assertions_path_constraints() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // JZ 0x4; NOP; NOP; RET
+        let img: [u8; 5] = [0x74, 0x02, 0x90, 0x90, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let jz = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let taken = ModeledBlock::read(&jingle, loaded.read(4, 1)).unwrap();
+        let path = jz.concat(taken).unwrap();
+        let zf = loaded.get_register("ZF").unwrap();
+        let solver = Solver::new(&z3);
+        let clear_zf_feasible = path.with_assertions(&solver, |solver, path| {
+            let zf = path.get_original_state().read_varnode(&zf).unwrap();
+            solver.assert(&zf._eq(&BV::from_u64(&z3, 0, 8)));
+            solver.check()
+        });
+        assert_eq!(clear_zf_feasible, SatResult::Unsat);
+        assert!(solver.get_assertions().is_empty());
+    }
+
+    #[test]
+    fn test_complexity() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, RBX
+        let copy_img: [u8; 3] = [0x48, 0x89, 0xd8];
+        // POPCNT RAX, RBX
+        let popcnt_img: [u8; 5] = [0xf3, 0x48, 0x0f, 0xb8, 0xc3];
+        let z3 = Context::new(&Config::new());
+        let copy = sleigh.initialize_with_image(copy_img.as_slice()).unwrap();
+        let popcnt = ctx_builder
+            .build(SLEIGH_ARCH)
+            .unwrap()
+            .initialize_with_image(popcnt_img.as_slice())
+            .unwrap();
+        let jingle = JingleContext::new(&z3, &copy);
+        let copy_block = ModeledBlock::read(&jingle, copy.read(0, 1)).unwrap();
+        let popcnt_block = ModeledBlock::read(&jingle, popcnt.read(0, 1)).unwrap();
+        let copy_complexity = copy_block.complexity();
+        let popcnt_complexity = popcnt_block.complexity();
+        assert_eq!(copy_complexity.ops, copy_block.get_ops().len());
+        assert!(copy_complexity
+            .spaces
+            .contains(&copy.get_register("RAX").unwrap().space_index));
+        assert!(popcnt_complexity.terms > copy_complexity.terms);
+    }
+
+    #[test]
+    fn test_branch_agrees_with() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // JZ 0x12 (short form, at 0); JZ 0x12 (near form, at 2)
+        let img: [u8; 8] = [0x74, 0x10, 0x0f, 0x84, 0x0a, 0x00, 0x00, 0x00];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let short = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let near = ModeledBlock::read(&jingle, loaded.read(2, 1)).unwrap();
+        let zf = loaded.get_register("ZF").unwrap();
+        let zf_set = short
+            .get_original_state()
+            .read_varnode(&zf)
+            .unwrap()
+            ._eq(&BV::from_u64(&z3, 1, 8));
+        let solver = Solver::new(&z3);
+        solver.assert(
+            &short
+                .get_original_state()
+                ._eq(near.get_original_state())
+                .unwrap(),
+        );
+        let agree = short.branch_agrees_with(&near).unwrap();
+        // both branch to 0x12 when ZF is set, but fall through to different addresses otherwise
+        solver.assert(&agree);
+        assert_eq!(solver.check_assumptions(&[zf_set.clone()]), SatResult::Sat);
+        assert_eq!(solver.check_assumptions(&[zf_set.not()]), SatResult::Unsat);
+    }
+
+    #[test]
+    fn test_can_write_to() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV [RAX], RBX
+        let img: [u8; 3] = [0x48, 0x89, 0x18];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let ram = loaded.get_code_space_idx();
+        let rax = loaded.get_register("RAX").unwrap();
+        let solver = Solver::new(&z3);
+        assert!(block.can_write_to(&solver, ram, 0x1000).unwrap());
+        assert!(!block
+            .can_write_to(&solver, rax.space_index, 0x1000)
+            .unwrap());
+        let pointer = block.get_original_state().read_varnode(&rax).unwrap();
+        solver.assert(&pointer.bvult(&BV::from_u64(&z3, 0x100, 64)));
+        assert!(!block.can_write_to(&solver, ram, 0x1000).unwrap());
+        assert!(block.can_write_to(&solver, ram, 0x80).unwrap());
+    }
+
+    #[test]
+    fn test_constants() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV EAX, 0x1234; ADD RAX, 0x10; RET
+        let img: [u8; 10] = [0xb8, 0x34, 0x12, 0x00, 0x00, 0x48, 0x83, 0xc0, 0x10, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 3)).unwrap();
+        let constants = block.constants();
+        assert!(constants.contains(&(0x1234, 4)));
+        assert!(constants.iter().any(|(val, _)| *val == 0x10));
+        let unique: HashSet<&(u64, usize)> = constants.iter().collect();
+        assert_eq!(unique.len(), constants.len());
+    }
+
+    #[test]
+    fn test_concat() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RBX, RAX; JMP 5; MOV RCX, RBX; RET
+        let img: [u8; 9] = [0x48, 0x89, 0xc3, 0xeb, 0x00, 0x48, 0x89, 0xd9, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let first = ModeledBlock::read(&jingle, loaded.read(0, 2)).unwrap();
+        let second = ModeledBlock::read(&jingle, loaded.read(5, 2)).unwrap();
+        let path = first.concat(second).unwrap();
+        assert_eq!(path.instructions.len(), 4);
+        assert_eq!(path.get_last_address(), 9);
+        let rax = loaded.get_register("RAX").unwrap();
+        let rcx = loaded.get_register("RCX").unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(
+            &path
+                .get_original_state()
+                .read_varnode(&rax)
+                .unwrap()
+                ._eq(&path.get_final_state().read_varnode(&rcx).unwrap())
+                .not(),
+        );
+        assert_eq!(solver.check(), SatResult::Unsat);
+        assert!(path.path_constraints().is_empty());
+    }
+
+    #[test]
+    fn test_concat_checks_exit() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // JZ 0x4; NOP; NOP; RET
+        let img: [u8; 5] = [0x74, 0x02, 0x90, 0x90, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let jz = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let nop = ModeledBlock::read(&jingle, loaded.read(3, 1)).unwrap();
+        assert!(matches!(
+            jz.clone().concat(nop),
+            Err(JingleError::DisconnectedBlocks)
+        ));
+        let taken = ModeledBlock::read(&jingle, loaded.read(4, 1)).unwrap();
+        let path = jz.concat(taken).unwrap();
+        assert_eq!(path.path_constraints().len(), 1);
+        let zf = path
+            .get_original_state()
+            .read_varnode(&loaded.get_register("ZF").unwrap())
+            .unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(&path.path_constraints()[0]);
+        solver.assert(&zf._eq(&BV::from_u64(&z3, 0, 8)));
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+}
@@ -4,7 +4,7 @@ use crate::modeling::ModelingContext;
 use crate::sleigh::{GeneralizedVarNode, VarNode};
 use serde::{Deserialize, Serialize};
 use std::ops::Not;
-use z3::ast::{Ast, BV};
+use z3::ast::{Ast, Bool, BV};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BlockConditionalBranchInfo {
     pub condition: VarNode,
@@ -100,6 +100,24 @@ impl BranchConstraint {
         self.last = UnconditionalBranch(new_last.clone())
     }
 
+    /// The destination of the first conditional branch in this constraint, if any.
+    pub fn conditional_target(&self) -> Option<&GeneralizedVarNode> {
+        self.conditional_branches.first().map(|c| &c.destination)
+    }
+
+    /// Returns a [Bool] assertion that the end-of-block behavior of `ctx` under this constraint
+    /// can be to transfer control to `addr`. Conditional branches are accounted for: both the
+    /// taken destination and the fallthrough are satisfiable targets.
+    pub fn satisfies_target<'ctx, 'a, T: ModelingContext<'ctx>>(
+        &self,
+        ctx: &'a T,
+        addr: u64,
+    ) -> Result<Bool<'ctx>, JingleError> {
+        let dest_bv = self.build_bv(ctx)?;
+        let addr_bv = BV::from_u64(ctx.get_jingle().z3, addr, dest_bv.get_size());
+        Ok(dest_bv._eq(&addr_bv))
+    }
+
     pub fn build_bv<'ctx, 'a, T: ModelingContext<'ctx>>(
         &self,
         ctx: &'a T,
@@ -149,3 +167,80 @@ impl BranchConstraint {
         Ok(dest_bv)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::modeling::{ModeledBlock, ModelingContext};
+    use crate::tests::SLEIGH_ARCH;
+    use crate::JingleContext;
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::GeneralizedVarNode;
+    use z3::{Config, Context, SatResult, Solver};
+
+    #[test]
+    fn test_cbranch_targets() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // JZ 0x12
+        let img: [u8; 2] = [0x74, 0x10];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 1)).unwrap();
+        let branch = block.get_branch_constraint();
+        match branch.conditional_target() {
+            Some(GeneralizedVarNode::Direct(d)) => assert_eq!(d.offset, 0x12),
+            _ => panic!("expected a direct conditional target"),
+        }
+        let solver = Solver::new(&z3);
+        for (addr, expected) in [
+            (0x12, SatResult::Sat),
+            (2, SatResult::Sat),
+            (5, SatResult::Unsat),
+        ] {
+            let target = branch.satisfies_target(&block, addr).unwrap();
+            assert_eq!(solver.check_assumptions(&[target]), expected);
+        }
+    }
+
+    #[test]
+    fn test_branch_targets_equivalent() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let z3 = Context::new(&Config::new());
+        // JZ 0x12 at 0: branches to 0x12 if ZF, otherwise 0x2
+        let jz_img: [u8; 2] = [0x74, 0x10];
+        let jz = ctx_builder
+            .build(SLEIGH_ARCH)
+            .unwrap()
+            .initialize_with_image(jz_img.as_slice())
+            .unwrap();
+        // JNZ 0x2 at 0x10: branches to 0x2 if !ZF, otherwise 0x12
+        let jnz_img: [u8; 2] = [0x75, 0xf0];
+        let mut jnz = ctx_builder
+            .build(SLEIGH_ARCH)
+            .unwrap()
+            .initialize_with_image(jnz_img.as_slice())
+            .unwrap();
+        jnz.set_base_address(0x10);
+        // JZ 0x14 at 0: branches to 0x14 if ZF, otherwise 0x2
+        let other_img: [u8; 2] = [0x74, 0x12];
+        let other = ctx_builder
+            .build(SLEIGH_ARCH)
+            .unwrap()
+            .initialize_with_image(other_img.as_slice())
+            .unwrap();
+        let jingle = JingleContext::new(&z3, &jz);
+        let jz_block = ModeledBlock::read(&jingle, jz.read(0, 1)).unwrap();
+        let jnz_block = ModeledBlock::read(&jingle, jnz.read(0x10, 1)).unwrap();
+        let other_block = ModeledBlock::read(&jingle, other.read(0, 1)).unwrap();
+        let solver = Solver::new(&z3);
+        assert!(jz_block
+            .branch_targets_equivalent(&jnz_block, &solver)
+            .unwrap());
+        assert!(!jz_block
+            .branch_targets_equivalent(&other_block, &solver)
+            .unwrap());
+    }
+}
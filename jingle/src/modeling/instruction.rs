@@ -1,6 +1,6 @@
 use crate::modeling::{ModelingContext, TranslationContext};
-use jingle_sleigh::Instruction;
 use jingle_sleigh::PcodeOperation;
+use jingle_sleigh::{Disassembly, Instruction};
 
 use std::collections::HashSet;
 
@@ -10,6 +10,7 @@ use crate::modeling::state::State;
 use crate::varnode::ResolvedVarnode;
 use crate::{JingleContext, JingleError};
 use jingle_sleigh::{SpaceInfo, SpaceManager};
+use z3::ast::{Ast, Bool};
 
 /// A `jingle` model of an individual SLEIGH instruction
 #[derive(Debug, Clone)]
@@ -21,16 +22,16 @@ pub struct ModeledInstruction<'ctx> {
     inputs: HashSet<ResolvedVarnode<'ctx>>,
     outputs: HashSet<ResolvedVarnode<'ctx>>,
     branch_builder: BranchConstraint,
+    overflow_checks: Vec<Bool<'ctx>>,
 }
 
 impl<'ctx> ModeledInstruction<'ctx> {
     pub fn new(instr: Instruction, jingle: &JingleContext<'ctx>) -> Result<Self, JingleError> {
         let original_state = State::new(jingle);
         let state = original_state.clone();
-        let next_vn = state.get_default_code_space_info().make_varnode(
-            instr.next_addr(),
-            state.get_default_code_space_info().index_size_bytes as usize,
-        );
+        let code_space = state.get_default_code_space_info()?;
+        let next_vn =
+            code_space.make_varnode(instr.next_addr(), code_space.index_size_bytes as usize);
         let mut model = Self {
             jingle: jingle.clone(),
             instr,
@@ -39,6 +40,7 @@ impl<'ctx> ModeledInstruction<'ctx> {
             inputs: Default::default(),
             outputs: Default::default(),
             branch_builder: BranchConstraint::new(&next_vn),
+            overflow_checks: Default::default(),
         };
         for x in model.instr.clone().ops.iter() {
             model.model_pcode_op(x)?;
@@ -46,9 +48,66 @@ impl<'ctx> ModeledInstruction<'ctx> {
         Ok(model)
     }
 
+    /// Model a list of [PcodeOperation]s that did not come from SLEIGH (e.g. hand-written or
+    /// parsed `p-code`), as though they were the semantics of a single instruction at `address`.
+    /// The synthetic instruction is treated as one byte long, so its fallthrough is `address + 1`.
+    pub fn from_ops(
+        jingle: &JingleContext<'ctx>,
+        address: u64,
+        ops: Vec<PcodeOperation>,
+    ) -> Result<Self, JingleError> {
+        let instr = Instruction {
+            disassembly: Disassembly {
+                mnemonic: "<synthetic>".to_string(),
+                args: "".to_string(),
+            },
+            ops,
+            length: 1,
+            address,
+        };
+        Self::new(instr, jingle)
+    }
+
+    /// Move this instruction over to the z3 context of `jingle`, which should have been produced
+    /// by [JingleContext::translate] on the context of this instruction.
+    pub fn translate<'dest>(&self, jingle: &JingleContext<'dest>) -> ModeledInstruction<'dest> {
+        ModeledInstruction {
+            jingle: jingle.clone(),
+            instr: self.instr.clone(),
+            state: self.state.translate(jingle),
+            original_state: self.original_state.translate(jingle),
+            inputs: self.inputs.iter().map(|i| i.translate(jingle.z3)).collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|o| o.translate(jingle.z3))
+                .collect(),
+            branch_builder: self.branch_builder.clone(),
+            overflow_checks: self
+                .overflow_checks
+                .iter()
+                .map(|c| c.translate(jingle.z3))
+                .collect(),
+        }
+    }
+
     pub fn fresh(&self) -> Result<Self, JingleError> {
         ModeledInstruction::new(self.instr.clone(), &self.jingle)
     }
+
+    /// Returns a [Bool] that holds exactly when one of the carry or overflow checks
+    /// (`INT_CARRY`, `INT_SCARRY`, `INT_SBORROW`) performed by this instruction fires, or `None`
+    /// if the instruction performs no such check. Each check is captured as it is modeled, so
+    /// it is unaffected by later operations overwriting the flag it sets.
+    pub fn overflow_condition(&self) -> Option<Bool<'ctx>> {
+        match self.overflow_checks.len() {
+            0 => None,
+            _ => {
+                let terms: Vec<&Bool> = self.overflow_checks.iter().collect();
+                Some(Bool::or(self.jingle.z3, terms.as_slice()))
+            }
+        }
+    }
 }
 
 impl SpaceManager for ModeledInstruction<'_> {
@@ -118,6 +177,10 @@ impl<'ctx> TranslationContext<'ctx> for ModeledInstruction<'ctx> {
     fn get_branch_builder(&mut self) -> &mut BranchConstraint {
         &mut self.branch_builder
     }
+
+    fn track_overflow_check(&mut self, check: Bool<'ctx>) {
+        self.overflow_checks.push(check);
+    }
 }
 
 /*impl<'ctx> From<&[ModeledInstruction<'ctx>]> for ModeledInstruction<'ctx>{
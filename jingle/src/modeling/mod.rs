@@ -2,7 +2,11 @@ use crate::error::JingleError;
 
 use crate::varnode::ResolvedVarnode::{Direct, Indirect};
 use crate::varnode::{ResolvedIndirectVarNode, ResolvedVarnode};
-use jingle_sleigh::{GeneralizedVarNode, PcodeOperation, SpaceManager, SpaceType};
+use jingle_sleigh::JingleSleighError::InvalidRegisterName;
+use jingle_sleigh::{
+    GeneralizedVarNode, PcodeOperation, RegisterManager, SpaceManager, SpaceType, UserOpManager,
+    VarNode,
+};
 use std::cmp::{min, Ordering};
 use std::collections::HashSet;
 use std::fmt::Debug;
@@ -10,6 +14,7 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 use std::ops::{Add, Neg};
 use tracing::instrument;
 use z3::ast::{Ast, Bool, BV};
+use z3::{SatResult, Solver};
 
 mod block;
 mod branch;
@@ -18,10 +23,10 @@ mod slice;
 mod state;
 
 use crate::JingleContext;
-pub use block::ModeledBlock;
+pub use block::{BlockComplexity, ModeledBlock};
 pub use branch::*;
 pub use instruction::ModeledInstruction;
-pub use state::State;
+pub use state::{SizedBV, State};
 
 /// `jingle` models straight-line traces of computations. This trait represents all the information
 /// needed to model a given trace.
@@ -59,6 +64,38 @@ pub trait ModelingContext<'ctx>: SpaceManager + Debug + Sized {
     /// all intermediate spaces that may be referenced
     fn get_outputs(&self) -> HashSet<ResolvedVarnode<'ctx>>;
 
+    /// The subset of [get_outputs] that is definitely written by this trace: direct varnodes, and
+    /// indirect varnodes whose pointer simplifies to a constant.
+    fn must_outputs(&self) -> HashSet<ResolvedVarnode<'ctx>> {
+        self.get_outputs()
+            .into_iter()
+            .filter(|vn| match vn {
+                Direct(_) => true,
+                Indirect(i) => i.pointer.simplify().is_const(),
+            })
+            .collect()
+    }
+
+    /// The subset of [get_outputs] that may or may not be written to a given location by this
+    /// trace: indirect varnodes with a symbolic pointer, which could alias other locations.
+    fn may_outputs(&self) -> HashSet<ResolvedVarnode<'ctx>> {
+        let must = self.must_outputs();
+        self.get_outputs()
+            .into_iter()
+            .filter(|vn| !must.contains(vn))
+            .collect()
+    }
+
+    /// The architectural locations written by this trace and read by [other]; i.e. the values
+    /// [other] depends on that this trace may clobber.
+    fn clobbers<T: ModelingContext<'ctx>>(&self, other: &T) -> HashSet<ResolvedVarnode<'ctx>> {
+        let inputs = other.get_inputs();
+        self.get_outputs()
+            .into_iter()
+            .filter(|vn| inputs.contains(vn) && self.should_varnode_constrain(vn))
+            .collect()
+    }
+
     ///`jingle` supports some rudimentary modeling of control flow; this will return a bitvector
     /// encapsulating the possible end-of-block behaviors of this trace
     fn get_branch_constraint(&self) -> &BranchConstraint;
@@ -153,7 +190,8 @@ pub trait ModelingContext<'ctx>: SpaceManager + Debug + Sized {
     }
 
     /// Returns an assertion that [other]'s end-branch behavior is able to branch to the same
-    /// destination as [self], given that [self] has branching behavior
+    /// destination as [self], given that [self] has branching behavior. Conditional branches of
+    /// both are accounted for.
     /// todo: should swap self and other to make this align better with [upholds_postcondition]
     fn branch_comparison<T: ModelingContext<'ctx>>(
         &self,
@@ -162,34 +200,46 @@ pub trait ModelingContext<'ctx>: SpaceManager + Debug + Sized {
         if !self.get_branch_constraint().has_branch() {
             Ok(None)
         } else {
-            let self_bv = self.get_branch_constraint().build_bv(self)?;
-            let other_bv = other.get_branch_constraint().build_bv(other)?;
-            let self_bv = zext_to_match(self_bv, &other_bv);
-            let other_bv = zext_to_match(other_bv, &self_bv);
-            let self_bv_metadata = self.get_branch_constraint().build_bv_metadata(self)?;
-            let other_bv_metadata = other.get_branch_constraint().build_bv_metadata(other)?;
-            let self_bv_metadata =
-                zext_to_match(self_bv_metadata.simplify(), &other_bv_metadata.simplify());
-            let other_bv_metadata = zext_to_match(other_bv_metadata, &self_bv_metadata);
-            Ok(Some(Bool::and(
-                self.get_jingle().z3,
-                &[
-                    self_bv._eq(&other_bv).simplify(),
-                    self_bv_metadata._eq(&other_bv_metadata).simplify(),
-                ],
-            )))
+            branch_agreement(self, other).map(Some)
         }
     }
+
+    /// Checks whether [self] and [other] always branch to the same destination when started
+    /// from the same state, including through conditional branches. Unlike
+    /// [branch_comparison](Self::branch_comparison), this holds for all inputs rather than
+    /// producing a satisfiable assertion. The given solver's assertions are respected but not
+    /// modified.
+    fn branch_targets_equivalent<T: ModelingContext<'ctx>>(
+        &self,
+        other: &T,
+        solver: &Solver<'ctx>,
+    ) -> Result<bool, JingleError> {
+        let same_input = self.get_original_state()._eq(other.get_original_state())?;
+        let differing_target = branch_destinations_eq(self, other)?.not();
+        match solver.check_assumptions(&[same_input, differing_target]) {
+            SatResult::Unsat => Ok(true),
+            SatResult::Sat => Ok(false),
+            SatResult::Unknown => Err(JingleError::SolverUnknown),
+        }
+    }
+
+    /// Returns a [Bool] assertion that the register `name` holds `value` in the final state of
+    /// this trace. Fails if the architecture has no register by that name.
+    fn assert_register_eq(&self, name: &str, value: u64) -> Result<Bool<'ctx>, JingleError> {
+        let state = self.get_final_state();
+        let register = state
+            .get_register(name)
+            .ok_or(JingleError::Sleigh(InvalidRegisterName))?;
+        let val = state.read_varnode(&register)?;
+        Ok(val._eq(&BV::from_u64(self.get_jingle().z3, value, val.get_size())))
+    }
+
     /// Returns a [Bool] assertion that the given trace's end-branch behavior is able to
-    /// branch to the given [u64]
+    /// branch to the given [u64]. This is shorthand for
+    /// [BranchConstraint::satisfies_target] on this trace's branch constraint, so conditional
+    /// branches are accounted for.
     fn can_branch_to_address(&self, addr: u64) -> Result<Bool<'ctx>, JingleError> {
-        let branch_constraint = self.get_branch_constraint().build_bv(self)?;
-        let addr_bv = BV::from_i64(
-            self.get_jingle().z3,
-            addr as i64,
-            branch_constraint.get_size(),
-        );
-        Ok(branch_constraint._eq(&addr_bv))
+        self.get_branch_constraint().satisfies_target(self, addr)
     }
 }
 
@@ -214,6 +264,10 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
     /// Get the helper object for encapsulating branch behavior
     fn get_branch_builder(&mut self) -> &mut BranchConstraint;
 
+    /// Records the condition under which a carry or overflow check (`INT_CARRY`, `INT_SCARRY`,
+    /// `INT_SBORROW`) fires, as it is modeled. Contexts that don't report these ignore them.
+    fn track_overflow_check(&mut self, _check: Bool<'ctx>) {}
+
     /// A helper function to both read and track an input [VarNode].
     fn read_and_track<'a>(&'a mut self, gen: GeneralizedVarNode) -> Result<BV<'ctx>, JingleError> {
         match gen {
@@ -263,9 +317,61 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
         Ok(())
     }
 
-    /// Apply the updates of a [PcodeOperation] on top of this context.
-    #[instrument(skip_all)]
+    /// If every input of `op` is a constant and it writes a value that [fold_constant] can
+    /// compute to a direct varnode, returns that varnode along with the value. The result of such
+    /// an operation is known ahead of time, so there is no reason to build its symbolic form.
+    fn constant_output(&self, op: &PcodeOperation) -> Option<(VarNode, u64)> {
+        let inputs = op.inputs();
+        let all_const = !inputs.is_empty()
+            && inputs.iter().all(|i| match i {
+                GeneralizedVarNode::Direct(vn) => self.is_const_space(vn.space_index),
+                GeneralizedVarNode::Indirect(_) => false,
+            });
+        match op.output() {
+            Some(GeneralizedVarNode::Direct(output)) if all_const && output.size <= 8 => {
+                let val = fold_constant(op)?;
+                Some((output, truncate_to_size(val, output.size)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the updates of a [PcodeOperation] on top of this context. Operations over only
+    /// constants are folded, writing a literal result rather than the term computing it.
+    /// If the [JingleContext] has an op observer registered, it is run afterward.
     fn model_pcode_op(&mut self, op: &PcodeOperation) -> Result<(), JingleError>
+    where
+        Self: Sized,
+    {
+        match self.constant_output(op) {
+            Some((output, val)) => {
+                if matches!(
+                    op,
+                    PcodeOperation::IntCarry { .. }
+                        | PcodeOperation::IntSignedCarry { .. }
+                        | PcodeOperation::IntSignedBorrow { .. }
+                ) {
+                    self.track_overflow_check(Bool::from_bool(self.get_jingle().z3, val != 0));
+                }
+                let val = BV::from_u64(self.get_jingle().z3, val, output.size as u32 * 8);
+                self.write(&GeneralizedVarNode::Direct(output), val)?;
+            }
+            None => self.model_pcode_op_symbolic(op)?,
+        }
+        if let Some(observer) = self.get_jingle().get_op_observer().cloned() {
+            let output = op
+                .output()
+                .map(|o| self.get_final_state().read(o))
+                .transpose()?;
+            observer(op, output.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Apply the updates of a [PcodeOperation] on top of this context, building the symbolic
+    /// term computing each of its outputs.
+    #[instrument(skip_all)]
+    fn model_pcode_op_symbolic(&mut self, op: &PcodeOperation) -> Result<(), JingleError>
     where
         Self: Sized,
     {
@@ -459,11 +565,13 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
             } => {
                 let in0 = self.read_and_track(input0.into())?;
                 let in1 = self.read_and_track(input1.into())?;
+                let outsize = output.size as u32;
                 // bool arg seems to be for whether this check is signed
                 let carry_bool = in0.bvadd_no_overflow(&in1, false);
+                self.track_overflow_check(carry_bool.not());
                 let out_bv = carry_bool.ite(
-                    &BV::from_i64(self.get_jingle().z3, 0, 8),
-                    &BV::from_i64(self.get_jingle().z3, 1, 8),
+                    &BV::from_i64(self.get_jingle().z3, 0, outsize * 8),
+                    &BV::from_i64(self.get_jingle().z3, 1, outsize * 8),
                 );
                 self.write(&output.into(), out_bv)
             }
@@ -474,11 +582,13 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
             } => {
                 let in0 = self.read_and_track(input0.into())?;
                 let in1 = self.read_and_track(input1.into())?;
+                let outsize = output.size as u32;
                 // bool arg seems to be for whether this check is signed
                 let carry_bool = in0.bvadd_no_overflow(&in1, true);
+                self.track_overflow_check(carry_bool.not());
                 let out_bv = carry_bool.ite(
-                    &BV::from_i64(self.get_jingle().z3, 0, 8),
-                    &BV::from_i64(self.get_jingle().z3, 1, 8),
+                    &BV::from_i64(self.get_jingle().z3, 0, outsize * 8),
+                    &BV::from_i64(self.get_jingle().z3, 1, outsize * 8),
                 );
                 self.write(&output.into(), out_bv)
             }
@@ -489,12 +599,14 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
             } => {
                 let in0 = self.read_and_track(input0.into())?;
                 let in1 = self.read_and_track(input1.into())?;
+                let outsize = output.size as u32;
                 // todo: need to do some experimentation as to what the intended
                 // meaning of "overflow" is in sleigh vs what it means in z3
                 let borrow_bool = in0.bvsub_no_underflow(&in1, true);
+                self.track_overflow_check(borrow_bool.not());
                 let out_bv = borrow_bool.ite(
-                    &BV::from_i64(self.get_jingle().z3, 0, 8),
-                    &BV::from_i64(self.get_jingle().z3, 1, 8),
+                    &BV::from_i64(self.get_jingle().z3, 0, outsize * 8),
+                    &BV::from_i64(self.get_jingle().z3, 1, outsize * 8),
                 );
                 self.write(&output.into(), out_bv)
             }
@@ -635,17 +747,43 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
                 self.write(&output.into(), result)
             }
             PcodeOperation::PopCount { input, output } => {
-                let size = output.size as u32;
                 let in0 = self.read_and_track(input.into())?;
-                let mut outbv = BV::from_i64(self.get_jingle().z3, 0, output.size as u32 * 8);
-                for i in 0..size * 8 {
-                    let extract = in0.extract(i, i);
-                    let extend = extract.zero_ext((size * 8) - 1);
-                    outbv = outbv.bvadd(&extend);
-                }
-
+                let outbv = popcount(&in0, output.size as u32 * 8);
                 self.write(&output.into(), outbv)
             }
+            PcodeOperation::Extract {
+                input0,
+                position,
+                size,
+                output,
+            } => {
+                let in0 = self.read_and_track(input0.into())?;
+                let (position, size) = bit_field(position.offset, size.offset, in0.get_size())?;
+                let field = in0.extract(position + size - 1, position);
+                self.write(&output.into(), fit_to_size(field, output.size as u32 * 8))
+            }
+            PcodeOperation::Insert {
+                input0,
+                input1,
+                position,
+                size,
+                output,
+            } => {
+                let in0 = self.read_and_track(input0.into())?;
+                let in1 = self.read_and_track(input1.into())?;
+                let width = in0.get_size();
+                let (position, size) = bit_field(position.offset, size.offset, width)?;
+                // the inserted bits are taken from the bottom of input1
+                bit_field(0, size as u64, in1.get_size())?;
+                let mut result = in1.extract(size - 1, 0);
+                if position > 0 {
+                    result = result.concat(&in0.extract(position - 1, 0));
+                }
+                if position + size < width {
+                    result = in0.extract(width - 1, position + size).concat(&result);
+                }
+                self.write(&output.into(), fit_to_size(result, output.size as u32 * 8))
+            }
             PcodeOperation::Branch { input } => {
                 self.get_branch_builder()
                     .set_last(&GeneralizedVarNode::from(input));
@@ -696,6 +834,21 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
                 }
             }
             PcodeOperation::CallOther { inputs, output } => {
+                let handler = inputs
+                    .first()
+                    .and_then(|idx| self.get_jingle().get_userop_name(idx.offset as usize))
+                    .and_then(|name| self.get_jingle().get_userop_handler(name))
+                    .cloned();
+                if let Some(handler) = handler {
+                    for input in inputs.iter().skip(1) {
+                        self.read_and_track(input.into())?;
+                    }
+                    handler(self.get_final_state_mut(), &inputs[1..], output.as_ref())?;
+                    if let Some(out) = output {
+                        self.track_output(&Direct(out.clone()));
+                    }
+                    return Ok(());
+                }
                 let mut hasher = DefaultHasher::new();
                 for vn in inputs {
                     vn.hash(&mut hasher);
@@ -708,7 +861,7 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
                     "const",
                     hash,
                     self.get_final_state()
-                        .get_default_code_space_info()
+                        .get_default_code_space_info()?
                         .index_size_bytes as usize,
                 )?;
                 let metadata = self
@@ -746,6 +899,184 @@ pub(crate) trait TranslationContext<'ctx>: ModelingContext<'ctx> {
     }
 }
 
+/// Zero-extend or truncate the given bitvector to exactly `bits` bits
+fn fit_to_size(bv: BV, bits: u32) -> BV {
+    match bv.get_size().cmp(&bits) {
+        Ordering::Less => bv.zero_ext(bits - bv.get_size()),
+        Ordering::Equal => bv,
+        Ordering::Greater => bv.extract(bits - 1, 0),
+    }
+}
+
+/// Count the set bits of `bv` into a bitvector of `bits` bits. The per-bit terms are summed
+/// pairwise, so the resulting term has logarithmic rather than linear depth in the width of `bv`;
+/// for 64-bit inputs this keeps simplification of `POPCOUNT` results from dominating solve time.
+fn popcount<'ctx>(bv: &BV<'ctx>, bits: u32) -> BV<'ctx> {
+    let mut terms: Vec<BV<'ctx>> = (0..bv.get_size())
+        .map(|i| bv.extract(i, i).zero_ext(bits - 1))
+        .collect();
+    while terms.len() > 1 {
+        terms = terms
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => a.bvadd(b),
+                [a] => a.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    terms
+        .pop()
+        .unwrap_or_else(|| BV::from_u64(bv.get_ctx(), 0, bits))
+}
+
+/// Computes the result of an integer or boolean `op` directly from the offsets of its constant
+/// inputs. Returns [None] for other operations, for operands wider than a [u64], and for inputs
+/// with no plain result (e.g. division by zero or an out-of-range shift), leaving those to the
+/// symbolic model.
+fn fold_constant(op: &PcodeOperation) -> Option<u64> {
+    if op.inputs().iter().any(|i| match i {
+        GeneralizedVarNode::Direct(vn) => vn.size == 0 || vn.size > 8,
+        GeneralizedVarNode::Indirect(_) => true,
+    }) {
+        return None;
+    }
+    let val = |vn: &VarNode| truncate_to_size(vn.offset, vn.size);
+    let signed = |vn: &VarNode| {
+        let shift = 64 - vn.size as u32 * 8;
+        ((vn.offset << shift) as i64) >> shift
+    };
+    let shift_amount = |input0: &VarNode, input1: &VarNode| {
+        let amount = val(input1);
+        (amount < input0.size as u64 * 8).then_some(amount as u32)
+    };
+    match op {
+        PcodeOperation::IntAdd { input0, input1, .. } => {
+            Some(val(input0).wrapping_add(val(input1)))
+        }
+        PcodeOperation::IntSub { input0, input1, .. } => {
+            Some(val(input0).wrapping_sub(val(input1)))
+        }
+        PcodeOperation::IntMult { input0, input1, .. } => {
+            Some(val(input0).wrapping_mul(val(input1)))
+        }
+        PcodeOperation::IntDiv { input0, input1, .. } => val(input0).checked_div(val(input1)),
+        PcodeOperation::IntRem { input0, input1, .. } => val(input0).checked_rem(val(input1)),
+        PcodeOperation::IntSignedDiv { input0, input1, .. } => {
+            signed(input0).checked_div(signed(input1)).map(|v| v as u64)
+        }
+        PcodeOperation::IntSignedRem { input0, input1, .. } => {
+            signed(input0).checked_rem(signed(input1)).map(|v| v as u64)
+        }
+        PcodeOperation::IntAnd { input0, input1, .. } => Some(val(input0) & val(input1)),
+        PcodeOperation::IntOr { input0, input1, .. } => Some(val(input0) | val(input1)),
+        PcodeOperation::IntXor { input0, input1, .. } => Some(val(input0) ^ val(input1)),
+        PcodeOperation::IntLeftShift { input0, input1, .. } => {
+            shift_amount(input0, input1).map(|a| val(input0) << a)
+        }
+        PcodeOperation::IntRightShift { input0, input1, .. } => {
+            shift_amount(input0, input1).map(|a| val(input0) >> a)
+        }
+        PcodeOperation::IntSignedRightShift { input0, input1, .. } => {
+            shift_amount(input0, input1).map(|a| (signed(input0) >> a) as u64)
+        }
+        PcodeOperation::IntZExt { input, .. } => Some(val(input)),
+        PcodeOperation::IntSExt { input, .. } => Some(signed(input) as u64),
+        PcodeOperation::IntEqual { input0, input1, .. } => {
+            Some((val(input0) == val(input1)) as u64)
+        }
+        PcodeOperation::IntNotEqual { input0, input1, .. } => {
+            Some((val(input0) != val(input1)) as u64)
+        }
+        PcodeOperation::IntLess { input0, input1, .. } => Some((val(input0) < val(input1)) as u64),
+        PcodeOperation::IntLessEqual { input0, input1, .. } => {
+            Some((val(input0) <= val(input1)) as u64)
+        }
+        PcodeOperation::IntSignedLess { input0, input1, .. } => {
+            Some((signed(input0) < signed(input1)) as u64)
+        }
+        PcodeOperation::IntSignedLessEqual { input0, input1, .. } => {
+            Some((signed(input0) <= signed(input1)) as u64)
+        }
+        PcodeOperation::IntCarry { input0, input1, .. } => {
+            let sum = val(input0) as u128 + val(input1) as u128;
+            Some((sum >> (input0.size * 8) != 0) as u64)
+        }
+        PcodeOperation::IntSignedCarry { input0, input1, .. } => {
+            let sum = signed(input0) as i128 + signed(input1) as i128;
+            let bits = input0.size as u32 * 8;
+            let in_range = sum >= -(1i128 << (bits - 1)) && sum < (1i128 << (bits - 1));
+            Some(!in_range as u64)
+        }
+        PcodeOperation::BoolAnd { input0, input1, .. } => Some(val(input0) & val(input1) & 1),
+        PcodeOperation::BoolOr { input0, input1, .. } => Some((val(input0) | val(input1)) & 1),
+        PcodeOperation::BoolXor { input0, input1, .. } => Some((val(input0) ^ val(input1)) & 1),
+        PcodeOperation::PopCount { input, .. } => Some(val(input).count_ones() as u64),
+        _ => None,
+    }
+}
+
+/// Validates the `size` bits starting at bit `position` as a field within a `width`-bit value,
+/// as used by `INSERT` and `EXTRACT`, returning them as [u32]s for use with z3
+fn bit_field(position: u64, size: u64, width: u32) -> Result<(u32, u32), JingleError> {
+    if size == 0 {
+        return Err(JingleError::ZeroSizedVarnode);
+    }
+    let out_of_range = JingleError::BitFieldOutOfRange {
+        position,
+        size,
+        width,
+    };
+    let (Ok(pos), Ok(len)) = (u32::try_from(position), u32::try_from(size)) else {
+        return Err(out_of_range);
+    };
+    match pos.checked_add(len) {
+        Some(end) if end <= width => Ok((pos, len)),
+        _ => Err(out_of_range),
+    }
+}
+
+/// Keeps only the low `size` bytes of `val`
+fn truncate_to_size(val: u64, size: usize) -> u64 {
+    if size >= 8 {
+        val
+    } else {
+        val & ((1u64 << (size * 8)) - 1)
+    }
+}
+
+/// Returns a [Bool] asserting that the end-of-block branch destinations of `a` and `b` are equal,
+/// with conditional branches of both accounted for
+fn branch_destinations_eq<'ctx, A: ModelingContext<'ctx>, B: ModelingContext<'ctx>>(
+    a: &A,
+    b: &B,
+) -> Result<Bool<'ctx>, JingleError> {
+    let a_bv = a.get_branch_constraint().build_bv(a)?;
+    let b_bv = b.get_branch_constraint().build_bv(b)?;
+    let a_bv = zext_to_match(a_bv, &b_bv);
+    let b_bv = zext_to_match(b_bv, &a_bv);
+    Ok(a_bv._eq(&b_bv).simplify())
+}
+
+/// Returns a [Bool] asserting that `a` and `b` branch to the same destination and that the
+/// metadata of their destinations agree
+pub(crate) fn branch_agreement<'ctx, A: ModelingContext<'ctx>, B: ModelingContext<'ctx>>(
+    a: &A,
+    b: &B,
+) -> Result<Bool<'ctx>, JingleError> {
+    let a_metadata = a.get_branch_constraint().build_bv_metadata(a)?.simplify();
+    let b_metadata = b.get_branch_constraint().build_bv_metadata(b)?.simplify();
+    let a_metadata = zext_to_match(a_metadata, &b_metadata);
+    let b_metadata = zext_to_match(b_metadata, &a_metadata);
+    Ok(Bool::and(
+        a.get_jingle().z3,
+        &[
+            branch_destinations_eq(a, b)?,
+            a_metadata._eq(&b_metadata).simplify(),
+        ],
+    ))
+}
+
 fn zext_to_match<'ctx>(bv1: BV<'ctx>, bv2: &BV<'ctx>) -> BV<'ctx> {
     if bv1.get_size() < bv2.get_size() {
         bv1.zero_ext(bv2.get_size() - bv1.get_size())
@@ -753,3 +1084,451 @@ fn zext_to_match<'ctx>(bv1: BV<'ctx>, bv2: &BV<'ctx>) -> BV<'ctx> {
         bv1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::modeling::{
+        bit_field, fold_constant, popcount, truncate_to_size, ModeledBlock, ModeledInstruction,
+        ModelingContext,
+    };
+    use crate::tests::SLEIGH_ARCH;
+    use crate::varnode::ResolvedVarnode;
+    use crate::{JingleContext, JingleError};
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{varnode, IndirectVarNode, PcodeOperation, RegisterManager, SpaceManager};
+    use z3::ast::{Ast, Bool, BV};
+    use z3::{Config, Context, SatResult, Solver};
+
+    #[test]
+    fn test_return_pops_stack() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // RET
+        let img: [u8; 1] = [0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let instr = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let rsp = loaded.get_register("RSP").unwrap();
+        let orig_rsp = instr.get_original_state().read_varnode(&rsp).unwrap();
+        let final_rsp = instr.get_final_state().read_varnode(&rsp).unwrap();
+        let return_address = instr
+            .get_original_state()
+            .read_varnode_indirect(&IndirectVarNode {
+                pointer_space_index: loaded.get_code_space_idx(),
+                pointer_location: rsp,
+                access_size_bytes: 8,
+            })
+            .unwrap();
+        let dest = instr.get_branch_constraint().build_bv(&instr).unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(&Bool::or(
+            &z3,
+            &[
+                &final_rsp
+                    ._eq(&orig_rsp.bvadd(&BV::from_u64(&z3, 8, 64)))
+                    .not(),
+                &dest._eq(&return_address).not(),
+            ],
+        ));
+        assert_eq!(solver.check(), SatResult::Unsat);
+    }
+
+    #[test]
+    fn test_overflow_condition() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // ADD RAX, RBX; MOV RAX, RBX
+        let img: [u8; 6] = [0x48, 0x01, 0xd8, 0x48, 0x89, 0xd8];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let add = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let mov = ModeledInstruction::new(loaded.instruction_at(3).unwrap(), &jingle).unwrap();
+        assert!(mov.overflow_condition().is_none());
+        let overflow = add.overflow_condition().unwrap();
+        let rax = add
+            .get_original_state()
+            .read_varnode(&loaded.get_register("RAX").unwrap())
+            .unwrap();
+        let rbx = add
+            .get_original_state()
+            .read_varnode(&loaded.get_register("RBX").unwrap())
+            .unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(&overflow);
+        assert_eq!(solver.check(), SatResult::Sat);
+        let small = Bool::and(
+            &z3,
+            &[
+                &rax._eq(&BV::from_u64(&z3, 1, 64)),
+                &rbx._eq(&BV::from_u64(&z3, 1, 64)),
+            ],
+        );
+        assert_eq!(solver.check_assumptions(&[small]), SatResult::Unsat);
+
+        // the check is captured when it is modeled, not read back from its output
+        let flag = varnode!(&sleigh, "unique"[0x100]:1).unwrap();
+        let ops = vec![
+            PcodeOperation::IntCarry {
+                input0: varnode!(&sleigh, #0xffff_ffff:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: flag.clone(),
+            },
+            PcodeOperation::Copy {
+                input: varnode!(&sleigh, #0:1).unwrap(),
+                output: flag,
+            },
+        ];
+        let overwritten = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        let fired = overwritten.overflow_condition().unwrap().simplify();
+        assert_eq!(fired.as_bool(), Some(true));
+        let wraps = rax._eq(&BV::from_u64(&z3, u64::MAX, 64));
+        assert_eq!(solver.check_assumptions(&[wraps]), SatResult::Sat);
+    }
+
+    #[test]
+    fn test_assert_register_eq() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let instr = ModeledInstruction::from_ops(&jingle, 0, vec![]).unwrap();
+        let solver = Solver::new(&z3);
+        let rdi = instr.assert_register_eq("RDI", 0x1000).unwrap();
+        solver.assert(&rdi);
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
+        let registers = instr.get_final_state().register_file_model(&model);
+        assert_eq!(registers.get("RDI"), Some(&0x1000));
+        let other = instr.assert_register_eq("RDI", 0x2000).unwrap();
+        assert_eq!(solver.check_assumptions(&[other]), SatResult::Unsat);
+        assert!(instr.assert_register_eq("fake", 0).is_err());
+    }
+
+    #[test]
+    fn test_from_ops() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let rax = varnode!(&sleigh, "register"[0]:8).unwrap();
+        let ops = vec![
+            PcodeOperation::Copy {
+                input: varnode!(&sleigh, #5:8).unwrap(),
+                output: rax.clone(),
+            },
+            PcodeOperation::IntAdd {
+                input0: rax.clone(),
+                input1: varnode!(&sleigh, #3:8).unwrap(),
+                output: rax.clone(),
+            },
+        ];
+        let instr = ModeledInstruction::from_ops(&jingle, 0x1000, ops).unwrap();
+        assert_eq!(instr.get_address(), 0x1000);
+        assert_eq!(instr.instr.next_addr(), 0x1001);
+        let val = instr
+            .get_final_state()
+            .read_varnode(&rax)
+            .unwrap()
+            .simplify();
+        assert_eq!(val.as_u64(), Some(8));
+    }
+
+    #[test]
+    fn test_clobbers() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let rax = varnode!(&sleigh, "register"[0]:8).unwrap();
+        let rbx = varnode!(&sleigh, "register"[0x18]:8).unwrap();
+        let writer = ModeledInstruction::from_ops(
+            &jingle,
+            0,
+            vec![PcodeOperation::Copy {
+                input: varnode!(&sleigh, #5:8).unwrap(),
+                output: rax.clone(),
+            }],
+        )
+        .unwrap();
+        let reader = ModeledInstruction::from_ops(
+            &jingle,
+            1,
+            vec![PcodeOperation::IntAdd {
+                input0: rbx.clone(),
+                input1: rax.clone(),
+                output: rbx.clone(),
+            }],
+        )
+        .unwrap();
+        let clobbered = writer.clobbers(&reader);
+        assert_eq!(clobbered.len(), 1);
+        assert!(clobbered.contains(&ResolvedVarnode::Direct(rax)));
+        assert!(reader.clobbers(&writer).is_empty());
+    }
+
+    #[test]
+    fn test_constant_fold() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let rax = varnode!(&sleigh, "register"[0]:8).unwrap();
+        let ops = vec![PcodeOperation::IntAdd {
+            input0: varnode!(&sleigh, #5:8).unwrap(),
+            input1: varnode!(&sleigh, #3:8).unwrap(),
+            output: rax.clone(),
+        }];
+        let instr = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        let registers = instr.get_final_state().get_space(rax.space_index).unwrap();
+        assert!(!format!("{:?}", registers).contains("bvadd"));
+        // the last byte stored is the most significant byte of the literal result
+        let stored = registers.children()[2].as_bv().unwrap();
+        assert_eq!(stored.as_u64(), Some(0));
+        let val = instr
+            .get_final_state()
+            .read_varnode(&rax)
+            .unwrap()
+            .simplify();
+        assert_eq!(val.as_u64(), Some(8));
+
+        let flag = varnode!(&sleigh, "unique"[0x100]:1).unwrap();
+        let quotient = varnode!(&sleigh, "unique"[0x200]:4).unwrap();
+        let ops = vec![
+            PcodeOperation::IntSignedLess {
+                input0: varnode!(&sleigh, #0xffff_ffff:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: flag.clone(),
+            },
+            // no plain result, so this is left to the symbolic model
+            PcodeOperation::IntDiv {
+                input0: varnode!(&sleigh, #5:4).unwrap(),
+                input1: varnode!(&sleigh, #0:4).unwrap(),
+                output: quotient.clone(),
+            },
+        ];
+        let instr = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        let state = instr.get_final_state();
+        assert_eq!(
+            state.read_varnode(&flag).unwrap().simplify().as_u64(),
+            Some(1)
+        );
+        assert!(format!("{:?}", state.read_varnode(&quotient).unwrap()).contains("bvudiv"));
+    }
+
+    #[test]
+    fn test_fold_constant() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let out = varnode!(&sleigh, "unique"[0]:4).unwrap();
+        let fold = |op| fold_constant(&op).map(|v| truncate_to_size(v, 4));
+        assert_eq!(
+            fold(PcodeOperation::IntSub {
+                input0: varnode!(&sleigh, #0:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: out.clone(),
+            }),
+            Some(0xffff_ffff)
+        );
+        assert_eq!(
+            fold(PcodeOperation::IntSExt {
+                input: varnode!(&sleigh, #0x80:1).unwrap(),
+                output: out.clone(),
+            }),
+            Some(0xffff_ff80)
+        );
+        assert_eq!(
+            fold(PcodeOperation::IntSignedCarry {
+                input0: varnode!(&sleigh, #0x7fff_ffff:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: out.clone(),
+            }),
+            Some(1)
+        );
+        assert_eq!(
+            fold(PcodeOperation::IntLeftShift {
+                input0: varnode!(&sleigh, #1:4).unwrap(),
+                input1: varnode!(&sleigh, #32:4).unwrap(),
+                output: out.clone(),
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_popcount() {
+        let z3 = Context::new(&Config::new());
+        for val in [
+            0u64,
+            1,
+            0x8000_0000_0000_0001,
+            0xdead_beef,
+            0x0f0f_0f0f,
+            u64::MAX,
+        ] {
+            let bv = BV::from_u64(&z3, val, 64);
+            // the linear chain of additions this encoding replaced
+            let mut linear = BV::from_u64(&z3, 0, 8);
+            for i in 0..64 {
+                linear = linear.bvadd(&bv.extract(i, i).zero_ext(7));
+            }
+            let balanced = popcount(&bv, 8).simplify();
+            assert_eq!(balanced.as_u64(), Some(val.count_ones() as u64));
+            assert_eq!(balanced.as_u64(), linear.simplify().as_u64());
+        }
+        // odd widths leave an unpaired term at some levels of the reduction
+        let bv = BV::from_u64(&z3, 0b101_1011, 7);
+        assert_eq!(popcount(&bv, 8).simplify().as_u64(), Some(5));
+    }
+
+    #[test]
+    fn test_insert_extract() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let field = varnode!(&sleigh, "unique"[0x100]:1).unwrap();
+        let ops = vec![
+            PcodeOperation::Extract {
+                output: field.clone(),
+                input0: varnode!(&sleigh, #0xabcd:2).unwrap(),
+                position: varnode!(&sleigh, #4:4).unwrap(),
+                size: varnode!(&sleigh, #8:4).unwrap(),
+            },
+            PcodeOperation::Insert {
+                output: varnode!(&sleigh, "unique"[0x200]:2).unwrap(),
+                input0: varnode!(&sleigh, #0xabcd:2).unwrap(),
+                input1: varnode!(&sleigh, #0x12:1).unwrap(),
+                position: varnode!(&sleigh, #4:4).unwrap(),
+                size: varnode!(&sleigh, #8:4).unwrap(),
+            },
+            PcodeOperation::Insert {
+                output: varnode!(&sleigh, "unique"[0x300]:2).unwrap(),
+                input0: varnode!(&sleigh, "unique"[0x200]:2).unwrap(),
+                input1: field,
+                position: varnode!(&sleigh, #4:4).unwrap(),
+                size: varnode!(&sleigh, #8:4).unwrap(),
+            },
+        ];
+        let instr = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        let state = instr.get_final_state();
+        for (vn, expected) in [
+            (varnode!(&sleigh, "unique"[0x100]:1).unwrap(), 0xbc),
+            (varnode!(&sleigh, "unique"[0x200]:2).unwrap(), 0xa12d),
+            (varnode!(&sleigh, "unique"[0x300]:2).unwrap(), 0xabcd),
+        ] {
+            let val = state.read_varnode(&vn).unwrap().simplify();
+            assert_eq!(val.as_u64(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_bit_field() {
+        assert_eq!(bit_field(4, 8, 16).unwrap(), (4, 8));
+        assert_eq!(bit_field(0, 16, 16).unwrap(), (0, 16));
+        assert!(matches!(
+            bit_field(4, 0, 16),
+            Err(JingleError::ZeroSizedVarnode)
+        ));
+        assert!(matches!(
+            bit_field(12, 8, 16),
+            Err(JingleError::BitFieldOutOfRange { .. })
+        ));
+        // would wrap to a small end position if added as u32s
+        assert!(matches!(
+            bit_field(u32::MAX as u64, 2, 16),
+            Err(JingleError::BitFieldOutOfRange { .. })
+        ));
+        assert!(matches!(
+            bit_field(1 << 32, 1, 16),
+            Err(JingleError::BitFieldOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unmodeled_op_error() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let ops = vec![PcodeOperation::New {
+            output: varnode!(&sleigh, "unique"[0x100]:8).unwrap(),
+            input: varnode!(&sleigh, #0:8).unwrap(),
+            size: None,
+        }];
+        let err = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap_err();
+        assert!(err.to_string().contains("NEW"));
+    }
+
+    #[test]
+    fn test_wide_carry_outputs() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let output = varnode!(&sleigh, "unique"[0x100]:2).unwrap();
+        let ops = vec![
+            PcodeOperation::IntCarry {
+                input0: varnode!(&sleigh, #0xffff_ffff:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: output.clone(),
+            },
+            PcodeOperation::IntSignedCarry {
+                input0: varnode!(&sleigh, #1:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: varnode!(&sleigh, "unique"[0x200]:2).unwrap(),
+            },
+            PcodeOperation::IntSignedBorrow {
+                input0: varnode!(&sleigh, #0x8000_0000:4).unwrap(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: varnode!(&sleigh, "unique"[0x300]:2).unwrap(),
+            },
+        ];
+        let instr = ModeledInstruction::from_ops(&jingle, 0, ops).unwrap();
+        let state = instr.get_final_state();
+        for (offset, expected) in [(0x100, 1), (0x200, 0), (0x300, 1)] {
+            let vn = varnode!(&sleigh, "unique"[offset]:2).unwrap();
+            let val = state.read_varnode(&vn).unwrap().simplify();
+            assert_eq!(val.get_size(), 16);
+            assert_eq!(val.as_u64(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn test_must_and_may_outputs() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, 1; MOV [RBX], RAX; RET
+        let img: [u8; 11] = [
+            0x48, 0xc7, 0xc0, 0x01, 0x00, 0x00, 0x00, 0x48, 0x89, 0x03, 0xc3,
+        ];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let block = ModeledBlock::read(&jingle, loaded.read(0, 3)).unwrap();
+        let rax = loaded.get_register("RAX").unwrap();
+        let must = block.must_outputs();
+        let may = block.may_outputs();
+        assert!(must.contains(&ResolvedVarnode::Direct(rax)));
+        assert!(must
+            .iter()
+            .all(|vn| matches!(vn, ResolvedVarnode::Direct(_))));
+        assert_eq!(may.len(), 1);
+        assert!(may
+            .iter()
+            .all(|vn| matches!(vn, ResolvedVarnode::Indirect(_))));
+        assert_eq!(must.len() + may.len(), block.get_outputs().len());
+    }
+}
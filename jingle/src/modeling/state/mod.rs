@@ -2,19 +2,27 @@ mod space;
 
 use crate::error::JingleError;
 use crate::error::JingleError::{
-    ConstantWrite, IndirectConstantRead, MismatchedWordSize, UnexpectedArraySort, UnmodeledSpace,
-    ZeroSizedVarnode,
+    ConstantWrite, IndirectConstantRead, MismatchedWordSize, NoCandidates, UnmodeledSpace,
 };
 
 use crate::modeling::state::space::ModeledSpace;
 use crate::varnode::ResolvedVarnode;
 use crate::JingleContext;
 use jingle_sleigh::{
-    GeneralizedVarNode, IndirectVarNode, RegisterManager, SpaceInfo, SpaceManager, SpaceType,
-    VarNode,
+    GeneralizedVarNode, IndirectVarNode, RegisterManager, SleighEndianness, SpaceInfo,
+    SpaceManager, SpaceType, UserOpManager, VarNode,
 };
-use std::ops::Add;
+use std::collections::BTreeMap;
 use z3::ast::{Array, Ast, Bool, BV};
+use z3::Model;
+
+/// A bitvector read out of a [State], along with the size (in bytes) of the location it was read
+/// from. Values of this type always satisfy `bv.get_size() == size_bytes * 8`.
+#[derive(Clone, Debug)]
+pub struct SizedBV<'ctx> {
+    pub bv: BV<'ctx>,
+    pub size_bytes: usize,
+}
 
 /// Represents the modeled combined memory state of the system. State
 /// is represented with Z3 formulas built up as select and store operations
@@ -23,6 +31,7 @@ use z3::ast::{Array, Ast, Bool, BV};
 pub struct State<'ctx> {
     jingle: JingleContext<'ctx>,
     spaces: Vec<ModeledSpace<'ctx>>,
+    assumptions: Vec<Bool<'ctx>>,
 }
 
 impl SpaceManager for State<'_> {
@@ -53,6 +62,12 @@ impl RegisterManager for State<'_> {
     }
 }
 
+impl UserOpManager for State<'_> {
+    fn get_userop_names(&self) -> &[String] {
+        self.jingle.get_userop_names()
+    }
+}
+
 impl<'ctx> State<'ctx> {
     pub fn new(jingle: &JingleContext<'ctx>) -> Self {
         let mut spaces: Vec<ModeledSpace> = Default::default();
@@ -62,6 +77,26 @@ impl<'ctx> State<'ctx> {
         Self {
             jingle: jingle.clone(),
             spaces,
+            assumptions: Default::default(),
+        }
+    }
+
+    /// Like [new](Self::new), but the array of each space is named `{prefix}_{space_name}` in
+    /// the produced SMT. z3 still appends a unique suffix, so states created with the same
+    /// prefix do not alias.
+    pub(crate) fn new_named(jingle: &JingleContext<'ctx>, prefix: &str) -> Self {
+        let spaces = jingle
+            .get_all_space_info()
+            .iter()
+            .map(|space_info| {
+                let name = format!("{}_{}", prefix, space_info.name);
+                ModeledSpace::new_named(jingle, space_info, &name)
+            })
+            .collect();
+        Self {
+            jingle: jingle.clone(),
+            spaces,
+            assumptions: Default::default(),
         }
     }
 
@@ -69,13 +104,21 @@ impl<'ctx> State<'ctx> {
         self.spaces
             .get(idx)
             .map(|u| u.get_space())
-            .ok_or(UnmodeledSpace)
+            .ok_or(UnmodeledSpace(idx))
+    }
+
+    /// Iterate over the array modeling each space, along with the index of that space
+    pub fn spaces(&self) -> impl Iterator<Item = (usize, &Array<'ctx>)> {
+        self.spaces
+            .iter()
+            .enumerate()
+            .map(|(idx, space)| (idx, space.get_space()))
     }
 
     pub fn read_varnode<'a>(&'a self, varnode: &VarNode) -> Result<BV<'ctx>, JingleError> {
         let space = self
             .get_space_info(varnode.space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(varnode.space_index))?;
         match space._type {
             SpaceType::IPTR_CONSTANT => Ok(BV::from_i64(
                 self.jingle.z3,
@@ -88,7 +131,10 @@ impl<'ctx> State<'ctx> {
                     varnode.offset as i64,
                     space.index_size_bytes * 8,
                 );
-                let arr = self.spaces.get(varnode.space_index).ok_or(UnmodeledSpace)?;
+                let arr = self
+                    .spaces
+                    .get(varnode.space_index)
+                    .ok_or(UnmodeledSpace(varnode.space_index))?;
                 arr.read_data(&offset, varnode.size)
             }
         }
@@ -97,14 +143,17 @@ impl<'ctx> State<'ctx> {
     pub fn read_varnode_metadata<'a>(&'a self, varnode: &VarNode) -> Result<BV<'ctx>, JingleError> {
         let space = self
             .get_space_info(varnode.space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(varnode.space_index))?;
 
         let offset = BV::from_i64(
             self.jingle.z3,
             varnode.offset as i64,
             space.index_size_bytes * 8,
         );
-        let arr = self.spaces.get(varnode.space_index).ok_or(UnmodeledSpace)?;
+        let arr = self
+            .spaces
+            .get(varnode.space_index)
+            .ok_or(UnmodeledSpace(varnode.space_index))?;
         arr.read_metadata(&offset, varnode.size)
     }
 
@@ -114,7 +163,7 @@ impl<'ctx> State<'ctx> {
     ) -> Result<BV<'ctx>, JingleError> {
         let pointer_space_info = self
             .get_space_info(indirect.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(indirect.pointer_space_index))?;
         if pointer_space_info._type == SpaceType::IPTR_CONSTANT {
             return Err(IndirectConstantRead);
         }
@@ -123,7 +172,7 @@ impl<'ctx> State<'ctx> {
         let space = self
             .spaces
             .get(indirect.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(indirect.pointer_space_index))?;
         space.read_data(&ptr, indirect.access_size_bytes)
     }
 
@@ -133,7 +182,7 @@ impl<'ctx> State<'ctx> {
     ) -> Result<BV<'ctx>, JingleError> {
         let pointer_space_info = self
             .get_space_info(indirect.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(indirect.pointer_space_index))?;
         if pointer_space_info._type == SpaceType::IPTR_CONSTANT {
             return Err(IndirectConstantRead);
         }
@@ -142,7 +191,7 @@ impl<'ctx> State<'ctx> {
         let space = self
             .spaces
             .get(indirect.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(indirect.pointer_space_index))?;
         space.read_metadata(&ptr, indirect.access_size_bytes)
     }
 
@@ -172,14 +221,14 @@ impl<'ctx> State<'ctx> {
         let info = self
             .jingle
             .get_space_info(dest.space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(dest.space_index))?;
         match info._type {
-            SpaceType::IPTR_CONSTANT => Err(ConstantWrite),
+            SpaceType::IPTR_CONSTANT => Err(ConstantWrite(dest.into())),
             _ => {
                 let space = self
                     .spaces
                     .get_mut(dest.space_index)
-                    .ok_or(UnmodeledSpace)?;
+                    .ok_or(UnmodeledSpace(dest.space_index))?;
                 space.write_data(
                     &val,
                     &BV::from_u64(self.jingle.z3, dest.offset, info.index_size_bytes * 8),
@@ -202,11 +251,11 @@ impl<'ctx> State<'ctx> {
         let space = self
             .spaces
             .get_mut(dest.space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(dest.space_index))?;
         let info = self
             .jingle
             .get_space_info(dest.space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(dest.space_index))?;
 
         space.write_metadata(
             &val,
@@ -224,13 +273,16 @@ impl<'ctx> State<'ctx> {
         let info = self
             .jingle
             .get_space_info(dest.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(dest.pointer_space_index))?;
 
         if info._type == SpaceType::IPTR_CONSTANT {
-            return Err(ConstantWrite);
+            return Err(ConstantWrite(dest.into()));
         }
         let ptr = self.read_varnode(&dest.pointer_location)?;
-        self.spaces[dest.pointer_space_index].write_data(&val, &ptr)?;
+        self.spaces
+            .get_mut(dest.pointer_space_index)
+            .ok_or(UnmodeledSpace(dest.pointer_space_index))?
+            .write_data(&val, &ptr)?;
         Ok(())
     }
 
@@ -242,16 +294,38 @@ impl<'ctx> State<'ctx> {
         let info = self
             .jingle
             .get_space_info(dest.pointer_space_index)
-            .ok_or(UnmodeledSpace)?;
+            .ok_or(UnmodeledSpace(dest.pointer_space_index))?;
 
         if info._type == SpaceType::IPTR_CONSTANT {
-            return Err(ConstantWrite);
+            return Err(ConstantWrite(dest.into()));
         }
         let ptr = self.read_varnode(&dest.pointer_location)?;
-        self.spaces[dest.pointer_space_index].write_metadata(&val, &ptr)?;
+        self.spaces
+            .get_mut(dest.pointer_space_index)
+            .ok_or(UnmodeledSpace(dest.pointer_space_index))?
+            .write_metadata(&val, &ptr)?;
         Ok(())
     }
 
+    /// Replace the contents of the region pointed to by `region` with a fresh bitvector constant
+    /// prefixed with `label`, returning that constant. Reads through any pointer into the region
+    /// will then be expressed in terms of `label` in the resulting SMT
+    /// (e.g. `(extract 7 0 stack_frame!0)`), rather than as selects against the anonymous array
+    /// of the whole space. Labels need not be unique: each call produces a distinct constant.
+    ///
+    /// Since the labeled constant is unconstrained, this does not change the meaning of a fresh
+    /// state. It does overwrite anything previously written to the region, so it should be
+    /// called before modeling any operations.
+    pub fn label_region(
+        &mut self,
+        region: &IndirectVarNode,
+        label: &str,
+    ) -> Result<BV<'ctx>, JingleError> {
+        let val = BV::fresh_const(self.jingle.z3, label, (region.access_size_bytes * 8) as u32);
+        self.write_varnode_indirect(region, val.clone())?;
+        Ok(val)
+    }
+
     pub fn read_resolved<'a, 'b: 'ctx, 'c>(
         &'a self,
         vn: &'a ResolvedVarnode<'b>,
@@ -259,28 +333,103 @@ impl<'ctx> State<'ctx> {
         match vn {
             ResolvedVarnode::Direct(d) => self.read_varnode(d),
             ResolvedVarnode::Indirect(indirect) => {
-                let array = self.get_space(indirect.pointer_space_idx)?;
-                (0..indirect.access_size_bytes)
-                    .map(|i| {
-                        array
-                            .select(&indirect.pointer.clone().add(i as u64))
-                            .as_bv()
-                            .ok_or(UnexpectedArraySort)
-                    })
-                    .reduce(|c, d| Ok(d?.concat(&c?)))
-                    .ok_or(ZeroSizedVarnode)?
+                let space = self
+                    .spaces
+                    .get(indirect.pointer_space_idx)
+                    .ok_or(UnmodeledSpace(indirect.pointer_space_idx))?;
+                space.read_data(&indirect.pointer, indirect.access_size_bytes)
             }
         }
     }
 
-    pub fn get_default_code_space(&self) -> &Array<'ctx> {
-        self.spaces[self.jingle.get_code_space_idx()].get_space()
+    /// Like [read_resolved](Self::read_resolved), but returns the value alongside its size,
+    /// failing if the bitvector read does not match the size of the [ResolvedVarnode].
+    pub fn read_resolved_sized<'a, 'b: 'ctx>(
+        &'a self,
+        vn: &'a ResolvedVarnode<'b>,
+    ) -> Result<SizedBV<'ctx>, JingleError> {
+        let size_bytes = match vn {
+            ResolvedVarnode::Direct(d) => d.size,
+            ResolvedVarnode::Indirect(i) => i.access_size_bytes,
+        };
+        let bv = self.read_resolved(vn)?;
+        if bv.get_size() as usize != size_bytes * 8 {
+            return Err(MismatchedWordSize);
+        }
+        Ok(SizedBV { bv, size_bytes })
     }
 
-    pub fn get_default_code_space_info(&self) -> &SpaceInfo {
-        self.jingle
-            .get_space_info(self.jingle.get_code_space_idx())
-            .unwrap()
+    /// Assume that the value of `vn` in this state is equal to one of the given concrete
+    /// `candidates`. Useful for modeling jump tables or enum-like values. The assumption is
+    /// recorded in [assumptions](Self::assumptions), which must be given to a solver alongside
+    /// any query over this state. An empty set of candidates is rejected, since it could never
+    /// be satisfied.
+    pub fn assume_one_of(&mut self, vn: &VarNode, candidates: &[u64]) -> Result<(), JingleError> {
+        if candidates.is_empty() {
+            return Err(NoCandidates);
+        }
+        let val = self.read_varnode(vn)?;
+        let terms: Vec<Bool> = candidates
+            .iter()
+            .map(|c| val._eq(&BV::from_u64(self.jingle.z3, *c, val.get_size())))
+            .collect();
+        let terms: Vec<&Bool> = terms.iter().collect();
+        self.assumptions
+            .push(Bool::or(self.jingle.z3, terms.as_slice()));
+        Ok(())
+    }
+
+    /// The assumptions made about this state, e.g. by [assume_one_of](Self::assume_one_of)
+    pub fn assumptions(&self) -> &[Bool<'ctx>] {
+        &self.assumptions
+    }
+
+    /// Produce a copy of this state in which the initial (symbolic) value of `vn` is replaced by
+    /// the constant `value`, with the contents of every space re-simplified. This allows for quick
+    /// concretization without consulting a solver.
+    pub fn substitute(&self, vn: &VarNode, value: u64) -> Result<State<'ctx>, JingleError> {
+        let space_info = self
+            .get_space_info(vn.space_index)
+            .ok_or(UnmodeledSpace(vn.space_index))?;
+        if space_info._type == SpaceType::IPTR_CONSTANT {
+            return Err(ConstantWrite(vn.into()));
+        }
+        let offset = BV::from_u64(self.jingle.z3, vn.offset, space_info.index_size_bytes * 8);
+        let val = BV::from_u64(self.jingle.z3, value, (vn.size * 8) as u32);
+        let space = self
+            .spaces
+            .get(vn.space_index)
+            .ok_or(UnmodeledSpace(vn.space_index))?;
+        let from = space.get_initial().clone();
+        let to = space.initial_with(&val, &offset)?;
+        let mut state = self.clone();
+        for space in state.spaces.iter_mut() {
+            space.substitute(&from, &to);
+        }
+        Ok(state)
+    }
+
+    /// Replace the `SLEIGH`-internal `unique` space with a fresh, unconstrained array.
+    /// Temporaries are not meant to outlive the instruction that writes them, so this is used
+    /// when chaining models together.
+    pub(crate) fn reset_internal_spaces(&mut self) {
+        let Some(idx) = self.jingle.get_unique_space_idx() else {
+            return;
+        };
+        if let (Some(space), Some(info)) =
+            (self.spaces.get_mut(idx), self.jingle.get_space_info(idx))
+        {
+            *space = ModeledSpace::new(&self.jingle, info);
+        }
+    }
+
+    pub fn get_default_code_space(&self) -> Result<&Array<'ctx>, JingleError> {
+        self.get_space(self.jingle.get_code_space_idx())
+    }
+
+    pub fn get_default_code_space_info(&self) -> Result<&SpaceInfo, JingleError> {
+        let idx = self.jingle.get_code_space_idx();
+        self.jingle.get_space_info(idx).ok_or(UnmodeledSpace(idx))
     }
 
     pub(crate) fn immediate_metadata_array(&self, val: bool, s: usize) -> BV<'ctx> {
@@ -311,6 +460,118 @@ impl<'ctx> State<'ctx> {
         Ok(Bool::and(self.jingle.z3, eq_terms.as_slice()))
     }
 
+    /// Returns a [Bool] asserting that the `len` bytes starting at `base` in the space with index
+    /// `space_index` are identical in [self] and [other]. An empty region is trivially equal.
+    pub fn region_eq(
+        &self,
+        other: &State<'ctx>,
+        space_index: usize,
+        base: u64,
+        len: usize,
+    ) -> Result<Bool<'ctx>, JingleError> {
+        if len == 0 {
+            return Ok(Bool::from_bool(self.jingle.z3, true));
+        }
+        let info = self
+            .get_space_info(space_index)
+            .ok_or(UnmodeledSpace(space_index))?;
+        if info._type == SpaceType::IPTR_CONSTANT {
+            return Err(UnmodeledSpace(space_index));
+        }
+        let offset = BV::from_u64(self.jingle.z3, base, info.index_size_bytes * 8);
+        let ours = self
+            .spaces
+            .get(space_index)
+            .ok_or(UnmodeledSpace(space_index))?
+            .read_data(&offset, len)?;
+        let theirs = other
+            .spaces
+            .get(space_index)
+            .ok_or(UnmodeledSpace(space_index))?
+            .read_data(&offset, len)?;
+        let terms: Vec<Bool> = (0..len as u32)
+            .map(|i| {
+                ours.extract(i * 8 + 7, i * 8)
+                    ._eq(&theirs.extract(i * 8 + 7, i * 8))
+            })
+            .collect();
+        let terms: Vec<&Bool> = terms.iter().collect();
+        Ok(Bool::and(self.jingle.z3, terms.as_slice()))
+    }
+
+    /// Evaluate every register of the architecture under the given [`Model`], keyed by
+    /// register name.
+    ///
+    /// Registers wider than 8 bytes are skipped. A register that is wholly contained in a
+    /// larger reported register (e.g. `EAX` inside `RAX`) is omitted in favor of the
+    /// enclosing one.
+    pub fn register_file_model(&self, model: &Model<'ctx>) -> BTreeMap<String, u64> {
+        let registers: Vec<(VarNode, String)> = self
+            .get_registers()
+            .into_iter()
+            .filter(|(vn, _)| vn.size <= 8)
+            .collect();
+        registers
+            .iter()
+            .filter(|(vn, _)| {
+                !registers
+                    .iter()
+                    .any(|(other, _)| other.size > vn.size && other.covers(vn))
+            })
+            .filter_map(|(vn, name)| {
+                let val = self.read_varnode(vn).ok()?;
+                let val = model.eval(&val, true)?.as_u64()?;
+                Some((name.clone(), val))
+            })
+            .collect()
+    }
+
+    /// Evaluate `vn` under the given [Model], returning its concrete bytes in the byte order of
+    /// the space it lives in (i.e. as they would appear in a memory image). Returns [None] if
+    /// `vn` cannot be read or does not evaluate to a concrete value.
+    pub fn concretize_varnode(&self, model: &Model<'ctx>, vn: &VarNode) -> Option<Vec<u8>> {
+        let endianness = self.get_space_info(vn.space_index)?.endianness;
+        let val = self.read_varnode(vn).ok()?;
+        let val = model.eval(&val, true)?;
+        // byte 0 here is the least significant byte
+        let mut bytes = (0..vn.size as u32)
+            .map(|i| {
+                val.extract(i * 8 + 7, i * 8)
+                    .simplify()
+                    .as_u64()
+                    .map(|b| b as u8)
+            })
+            .collect::<Option<Vec<u8>>>()?;
+        if matches!(endianness, SleighEndianness::Big) {
+            bytes.reverse();
+        }
+        Some(bytes)
+    }
+
+    /// Move this state over to the z3 context of `jingle`, which should have been produced by
+    /// [JingleContext::translate] on the context of this state.
+    pub fn translate<'dest>(&self, jingle: &JingleContext<'dest>) -> State<'dest> {
+        State {
+            jingle: jingle.clone(),
+            spaces: self.spaces.iter().map(|s| s.translate(jingle)).collect(),
+            assumptions: self
+                .assumptions
+                .iter()
+                .map(|a| a.translate(jingle.z3))
+                .collect(),
+        }
+    }
+
+    /// Produce a copy of this state in which the array modeling each space has been simplified.
+    /// This can drastically shrink the size of formulas built from this state.
+    pub fn simplify(&self) -> State<'ctx> {
+        State {
+            jingle: self.jingle.clone(),
+            spaces: self.spaces.iter().map(|s| s.simplify()).collect(),
+            assumptions: self.assumptions.iter().map(|a| a.simplify()).collect(),
+        }
+    }
+
     pub fn fmt_smt_arrays(&self) -> String {
         let mut lines = vec![];
         for x in &self.spaces {
@@ -319,3 +580,407 @@ impl<'ctx> State<'ctx> {
         lines.join("\n")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::modeling::{ModeledInstruction, ModelingContext, State};
+    use crate::tests::SLEIGH_ARCH;
+    use crate::varnode::{ResolvedIndirectVarNode, ResolvedVarnode};
+    use crate::{JingleContext, JingleError};
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{
+        varnode, GeneralizedVarNode, IndirectVarNode, RegisterManager, SpaceManager,
+    };
+    use z3::ast::{Ast, BV};
+    use z3::{Config, Context, SatResult, Solver};
+
+    #[test]
+    fn test_constant_write() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let dest = IndirectVarNode {
+            pointer_space_index: varnode!(&sleigh, #0:8).unwrap().space_index,
+            pointer_location: state.get_register("RAX").unwrap(),
+            access_size_bytes: 8,
+        };
+        let err = state
+            .write_varnode_indirect(&dest, BV::from_u64(&z3, 0, 64))
+            .unwrap_err();
+        match err {
+            JingleError::ConstantWrite(GeneralizedVarNode::Indirect(i)) => assert_eq!(i, dest),
+            _ => panic!("expected a constant write error"),
+        }
+    }
+
+    #[test]
+    fn test_region_eq() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // PUSH RAX
+        let img: [u8; 1] = [0x50];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let instr = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let original = instr.get_original_state();
+        let last = instr.get_final_state();
+        let rsp = original.get_register("RSP").unwrap();
+        let rsp_val = original
+            .read_varnode(&rsp)
+            .unwrap()
+            ._eq(&BV::from_u64(&z3, 0x1000, 64));
+        let ram = loaded.get_code_space_idx();
+        let solver = Solver::new(&z3);
+        solver.assert(&rsp_val);
+        // everything at and above the original stack pointer is untouched
+        let above = original.region_eq(last, ram, 0x1000, 0x20).unwrap();
+        assert_eq!(solver.check_assumptions(&[above.not()]), SatResult::Unsat);
+        // the pushed slot may differ
+        let pushed = original.region_eq(last, ram, 0xff8, 8).unwrap();
+        assert_eq!(solver.check_assumptions(&[pushed.not()]), SatResult::Sat);
+        let empty = original.region_eq(last, ram, 0xff8, 0).unwrap();
+        assert_eq!(empty.simplify().as_bool(), Some(true));
+    }
+
+    #[test]
+    fn test_label_region() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let rsp = state.get_register("RSP").unwrap();
+        let frame = IndirectVarNode {
+            pointer_space_index: state.get_code_space_idx(),
+            pointer_location: rsp.clone(),
+            access_size_bytes: 16,
+        };
+        let label = state.label_region(&frame, "stack_frame").unwrap();
+        assert_eq!(label.get_size(), 128);
+        let top = IndirectVarNode {
+            access_size_bytes: 8,
+            ..frame
+        };
+        let read = state.read_varnode_indirect(&top).unwrap();
+        let solver = Solver::new(&z3);
+        solver.assert(&read._eq(&BV::from_u64(&z3, 0, 64)));
+        assert!(solver.to_smt2().contains("stack_frame"));
+        // reusing a label must not alias the earlier region's contents
+        let other = state.label_region(&frame, "stack_frame").unwrap();
+        assert_eq!(
+            solver.check_assumptions(&[label._eq(&other).not()]),
+            SatResult::Sat
+        );
+    }
+
+    #[test]
+    fn test_reset_internal_spaces() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        assert_eq!(jingle.get_unique_space_idx(), sleigh.get_unique_space_idx());
+        let mut state = State::new(&jingle);
+        let tmp = varnode!(&sleigh, "unique"[0x100]:8).unwrap();
+        let rax = state.get_register("RAX").unwrap();
+        let one = BV::from_u64(&z3, 1, 64);
+        state.write_varnode(&tmp, one.clone()).unwrap();
+        state.write_varnode(&rax, one).unwrap();
+        state.reset_internal_spaces();
+        let tmp_val = state.read_varnode(&tmp).unwrap().simplify();
+        assert!(tmp_val.as_u64().is_none());
+        let rax_val = state.read_varnode(&rax).unwrap().simplify();
+        assert_eq!(rax_val.as_u64(), Some(1));
+    }
+
+    #[test]
+    fn test_spaces() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = State::new(&jingle);
+        assert_eq!(state.spaces().count(), state.get_all_space_info().len());
+        for (idx, array) in state.spaces() {
+            assert_eq!(array, state.get_space(idx).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let rax = state.get_register("RAX").unwrap();
+        for i in 0..4 {
+            state.write_varnode(&rax, BV::from_u64(&z3, i, 64)).unwrap();
+        }
+        let simplified = state.simplify();
+        let arrays_len = |s: &State| {
+            s.spaces()
+                .map(|(_, a)| format!("{:?}", a).len())
+                .sum::<usize>()
+        };
+        assert!(arrays_len(&simplified) < arrays_len(&state));
+        let val = simplified.read_varnode(&rax).unwrap().simplify();
+        assert_eq!(val.as_u64(), Some(3));
+    }
+
+    #[test]
+    fn test_concretize_varnode() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = State::new(&jingle);
+        let solver = Solver::new(&z3);
+        let rax = state.get_register("RAX").unwrap();
+        solver.assert(&state.read_varnode(&rax).unwrap()._eq(&BV::from_u64(
+            &z3,
+            0x0102_0304_0506_0708,
+            64,
+        )));
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
+        assert_eq!(
+            state.concretize_varnode(&model, &rax),
+            Some(vec![8, 7, 6, 5, 4, 3, 2, 1])
+        );
+    }
+
+    #[test]
+    fn test_register_file_model() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = State::new(&jingle);
+        let solver = Solver::new(&z3);
+        let rax = state.get_register("RAX").unwrap();
+        let rbx = state.get_register("RBX").unwrap();
+        solver.assert(
+            &state
+                .read_varnode(&rax)
+                .unwrap()
+                ._eq(&BV::from_u64(&z3, 0xdead_beef, 64)),
+        );
+        solver.assert(
+            &state
+                .read_varnode(&rbx)
+                .unwrap()
+                ._eq(&BV::from_u64(&z3, 7, 64)),
+        );
+        assert_eq!(solver.check(), SatResult::Sat);
+        let model = solver.get_model().unwrap();
+        let registers = state.register_file_model(&model);
+        assert_eq!(registers.get("RAX"), Some(&0xdead_beef));
+        assert_eq!(registers.get("RBX"), Some(&7));
+        assert!(registers.contains_key("RCX"));
+        assert!(!registers.contains_key("EAX"));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // LEA RAX, [RDI + 1]
+        let img: [u8; 4] = [0x48, 0x8d, 0x47, 0x01];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &loaded);
+        let instr = ModeledInstruction::new(loaded.instruction_at(0).unwrap(), &jingle).unwrap();
+        let rax = loaded.get_register("RAX").unwrap();
+        let rdi = loaded.get_register("RDI").unwrap();
+        let state = instr.get_final_state();
+        assert!(!state.read_varnode(&rax).unwrap().simplify().is_const());
+        let state = state.substitute(&rdi, 0x41).unwrap();
+        let val = state.read_varnode(&rax).unwrap().simplify();
+        assert_eq!(val.as_u64(), Some(0x42));
+    }
+
+    #[test]
+    fn test_assume_one_of() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let rax = sleigh.get_register("RAX").unwrap();
+        assert!(matches!(
+            state.assume_one_of(&rax, &[]),
+            Err(JingleError::NoCandidates)
+        ));
+        assert!(state.assumptions().is_empty());
+        state.assume_one_of(&rax, &[2, 4]).unwrap();
+        let solver = Solver::new(&z3);
+        for assumption in state.assumptions() {
+            solver.assert(assumption);
+        }
+        let val = state.read_varnode(&rax).unwrap();
+        for (candidate, expected) in [
+            (2, SatResult::Sat),
+            (4, SatResult::Sat),
+            (3, SatResult::Unsat),
+        ] {
+            let eq = val._eq(&BV::from_u64(&z3, candidate, 64));
+            assert_eq!(solver.check_assumptions(&[eq]), expected);
+        }
+    }
+
+    #[test]
+    fn test_read_resolved_sized() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = State::new(&jingle);
+        let rbx = sleigh.get_register("RBX").unwrap();
+        let indirect = ResolvedVarnode::Indirect(ResolvedIndirectVarNode {
+            pointer_space_idx: sleigh.get_code_space_idx(),
+            pointer: state.read_varnode(&rbx).unwrap(),
+            pointer_location: rbx.clone(),
+            access_size_bytes: 4,
+        });
+        let sized = state.read_resolved_sized(&indirect).unwrap();
+        assert_eq!(sized.size_bytes, 4);
+        assert_eq!(sized.bv.get_size(), 32);
+        let sized = state
+            .read_resolved_sized(&ResolvedVarnode::Direct(rbx))
+            .unwrap();
+        assert_eq!(sized.size_bytes, 8);
+        assert_eq!(sized.bv.get_size(), 64);
+    }
+
+    #[test]
+    fn test_big_endian_indirect_read() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build("MIPS:BE:32:default").unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let a0 = sleigh.get_register("a0").unwrap();
+        let ram = sleigh.get_code_space_idx();
+        let indirect = IndirectVarNode {
+            pointer_space_index: ram,
+            pointer_location: a0.clone(),
+            access_size_bytes: 4,
+        };
+        state
+            .write_varnode_indirect(&indirect, BV::from_u64(&z3, 0xdead_beef, 32))
+            .unwrap();
+        let pointer = state.read_varnode(&a0).unwrap();
+        let resolved = ResolvedVarnode::Indirect(ResolvedIndirectVarNode {
+            pointer_space_idx: ram,
+            pointer: pointer.clone(),
+            pointer_location: a0,
+            access_size_bytes: 4,
+        });
+        let val = state.read_resolved(&resolved).unwrap().simplify();
+        assert_eq!(val.as_u64(), Some(0xdead_beef));
+        let first_byte = ResolvedVarnode::Indirect(ResolvedIndirectVarNode {
+            pointer_space_idx: ram,
+            pointer,
+            pointer_location: sleigh.get_register("a0").unwrap(),
+            access_size_bytes: 1,
+        });
+        let val = state.read_resolved(&first_byte).unwrap().simplify();
+        assert_eq!(val.as_u64(), Some(0xde));
+    }
+
+    #[test]
+    fn test_invalid_indirect_space() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let bad_index = sleigh.get_all_space_info().len() + 10;
+        let indirect = IndirectVarNode {
+            pointer_space_index: bad_index,
+            pointer_location: sleigh.get_register("RBX").unwrap(),
+            access_size_bytes: 4,
+        };
+        let val = BV::from_u64(&z3, 0, 32);
+        assert!(matches!(
+            state.write_varnode_indirect(&indirect, val.clone()),
+            Err(JingleError::UnmodeledSpace(i)) if i == bad_index
+        ));
+        assert!(matches!(
+            state.write_varnode_metadata_indirect(&indirect, val.extract(3, 0)),
+            Err(JingleError::UnmodeledSpace(i)) if i == bad_index
+        ));
+        assert!(matches!(
+            state.read_varnode_indirect(&indirect),
+            Err(JingleError::UnmodeledSpace(i)) if i == bad_index
+        ));
+    }
+
+    #[test]
+    fn test_invalid_direct_space() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let state = State::new(&jingle);
+        let bad_index = sleigh.get_all_space_info().len() + 3;
+        let mut vn = sleigh.get_register("RBX").unwrap();
+        vn.space_index = bad_index;
+        assert!(matches!(
+            state.read_varnode(&vn),
+            Err(JingleError::UnmodeledSpace(i)) if i == bad_index
+        ));
+        assert!(matches!(
+            state.get_space(bad_index),
+            Err(JingleError::UnmodeledSpace(i)) if i == bad_index
+        ));
+        let code_space = state.get_default_code_space_info().unwrap();
+        assert_eq!(code_space.index, sleigh.get_code_space_idx());
+        assert_eq!(
+            state.get_default_code_space().unwrap(),
+            state.get_space(code_space.index).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_indirect_metadata() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut state = State::new(&jingle);
+        let indirect = IndirectVarNode {
+            pointer_space_index: sleigh.get_code_space_idx(),
+            pointer_location: sleigh.get_register("RBX").unwrap(),
+            access_size_bytes: 4,
+        };
+        let metadata = state.immediate_metadata_array(true, 4);
+        state
+            .write_varnode_metadata_indirect(&indirect, metadata)
+            .unwrap();
+        let read = state
+            .read_varnode_metadata_indirect(&indirect)
+            .unwrap()
+            .simplify();
+        assert_eq!(read.as_u64(), Some(0b1111));
+    }
+}
@@ -8,6 +8,10 @@ use z3::Sort;
 /// SLEIGH models programs using many spaces. This struct serves as a helper for modeling a single
 /// space. `jingle` uses an SMT Array sort to model a space.
 ///
+/// The array is always byte-granular, even for word-addressed spaces: offsets into such a space
+/// are in units of words, so they are scaled by the word size of the space before indexing the
+/// array.
+///
 /// `jingle` also maintains a separate Array holding "metadata" for the space. For right now, this
 /// metadata has a single-bit bitvector as its word type, and it is only used for tracking whether
 /// a given value originated from a CALLOTHER operation. This is necessary for distinguishing
@@ -15,6 +19,8 @@ use z3::Sort;
 #[derive(Clone, Debug)]
 pub(crate) struct ModeledSpace<'ctx> {
     endianness: SleighEndianness,
+    /// The unconstrained array representing the contents of this space before any writes
+    initial: Array<'ctx>,
     data: Array<'ctx>,
     #[allow(unused)]
     metadata: Array<'ctx>,
@@ -24,16 +30,44 @@ pub(crate) struct ModeledSpace<'ctx> {
 impl<'ctx> ModeledSpace<'ctx> {
     /// Create a new modeling space with the given z3 context, using the provided space metadata
     pub(crate) fn new(jingle: &JingleContext<'ctx>, space_info: &SpaceInfo) -> Self {
-        let domain = Sort::bitvector(jingle.z3, space_info.index_size_bytes * 8);
-        let range = Sort::bitvector(jingle.z3, space_info.word_size_bytes * 8);
+        Self::new_named(jingle, space_info, &space_info.name)
+    }
+
+    /// Like [new](Self::new), but the initial array is named using `name` as a prefix
+    /// rather than the name of the space
+    pub(crate) fn new_named(
+        jingle: &JingleContext<'ctx>,
+        space_info: &SpaceInfo,
+        name: &str,
+    ) -> Self {
+        let domain = Sort::bitvector(
+            jingle.z3,
+            space_info.index_size_bytes * 8 + word_index_bits(space_info.word_size_bytes),
+        );
+        let range = Sort::bitvector(jingle.z3, 8);
+        let initial = Array::fresh_const(jingle.z3, name, &domain, &range);
         Self {
             endianness: space_info.endianness,
-            data: Array::fresh_const(jingle.z3, &space_info.name, &domain, &range),
+            data: initial.clone(),
+            initial,
             metadata: Array::const_array(jingle.z3, &domain, &BV::from_u64(jingle.z3, 0, 1)),
             space_info: space_info.clone(),
         }
     }
 
+    /// Convert an offset into this space (in units of words) into an index into the byte-granular
+    /// array. The array domain is widened to hold the byte index of every word, so the offset is
+    /// zero-extended before scaling.
+    fn byte_offset(&self, offset: &BV<'ctx>) -> BV<'ctx> {
+        match self.space_info.word_size_bytes {
+            1 => offset.clone(),
+            w => {
+                let offset = offset.zero_ext(word_index_bits(w));
+                offset.bvmul(&BV::from_u64(offset.get_ctx(), w as u64, offset.get_size()))
+            }
+        }
+    }
+
     /// Get the z3 Array for this space
     pub(crate) fn get_space(&self) -> &Array<'ctx> {
         &self.data
@@ -48,7 +82,12 @@ impl<'ctx> ModeledSpace<'ctx> {
         if offset.get_size() != self.space_info.index_size_bytes * 8 {
             return Err(MismatchedAddressSize);
         }
-        read_from_array(&self.data, offset, size_bytes, self.endianness)
+        read_from_array(
+            &self.data,
+            &self.byte_offset(offset),
+            size_bytes,
+            self.endianness,
+        )
     }
 
     /// Read [size_bytes] bytes worth of metadata from the given BV [offset], using the endianness
@@ -61,7 +100,12 @@ impl<'ctx> ModeledSpace<'ctx> {
         if offset.get_size() != self.space_info.index_size_bytes * 8 {
             return Err(MismatchedAddressSize);
         }
-        read_from_array(&self.metadata, offset, size_bytes, self.endianness)
+        read_from_array(
+            &self.metadata,
+            &self.byte_offset(offset),
+            size_bytes,
+            self.endianness,
+        )
     }
 
     /// Write the given bitvector of data to the given bitvector offset
@@ -73,7 +117,8 @@ impl<'ctx> ModeledSpace<'ctx> {
         if offset.get_size() != self.space_info.index_size_bytes * 8 {
             return Err(MismatchedAddressSize);
         }
-        self.data = write_to_array::<8>(&self.data, val, offset, self.endianness);
+        self.data =
+            write_to_array::<8>(&self.data, val, &self.byte_offset(offset), self.endianness);
         Ok(())
     }
 
@@ -86,15 +131,75 @@ impl<'ctx> ModeledSpace<'ctx> {
         if offset.get_size() != self.space_info.index_size_bytes * 8 {
             return Err(MismatchedAddressSize);
         }
-        self.metadata = write_to_array::<1>(&self.metadata, val, offset, self.endianness);
+        self.metadata = write_to_array::<1>(
+            &self.metadata,
+            val,
+            &self.byte_offset(offset),
+            self.endianness,
+        );
         Ok(())
     }
 
+    /// Get the unconstrained z3 Array this space started from
+    pub(crate) fn get_initial(&self) -> &Array<'ctx> {
+        &self.initial
+    }
+
+    /// Get a copy of the initial array of this space with the given bitvector of data written at
+    /// the given offset
+    pub(crate) fn initial_with(
+        &self,
+        val: &BV<'ctx>,
+        offset: &BV<'ctx>,
+    ) -> Result<Array<'ctx>, JingleError> {
+        if offset.get_size() != self.space_info.index_size_bytes * 8 {
+            return Err(MismatchedAddressSize);
+        }
+        Ok(write_to_array::<8>(
+            &self.initial,
+            val,
+            &self.byte_offset(offset),
+            self.endianness,
+        ))
+    }
+
+    /// Replace every occurrence of `from` in the contents of this space with `to`,
+    /// simplifying the result
+    pub(crate) fn substitute(&mut self, from: &Array<'ctx>, to: &Array<'ctx>) {
+        self.data = self.data.substitute(&[(from, to)]).simplify();
+    }
+
+    /// Get a copy of this space with its data and metadata arrays simplified
+    pub(crate) fn simplify(&self) -> Self {
+        Self {
+            data: self.data.simplify(),
+            metadata: self.metadata.simplify(),
+            ..self.clone()
+        }
+    }
+
+    /// Move this space over to the z3 context of `jingle`
+    pub(crate) fn translate<'dest>(&self, jingle: &JingleContext<'dest>) -> ModeledSpace<'dest> {
+        ModeledSpace {
+            endianness: self.endianness,
+            initial: self.initial.translate(jingle.z3),
+            data: self.data.translate(jingle.z3),
+            metadata: self.metadata.translate(jingle.z3),
+            space_info: self.space_info.clone(),
+        }
+    }
+
     pub(crate) fn fmt_smt_array(&self) -> String {
         format!("{:?}", self.data.simplify())
     }
 }
 
+/// The number of extra index bits needed to address each byte of a word of `word_size_bytes`
+/// bytes, i.e. `ceil(log2(word_size_bytes))`
+fn word_index_bits(word_size_bytes: u32) -> u32 {
+    word_size_bytes.max(1).next_power_of_two().trailing_zeros()
+}
+
 fn read_from_array<'ctx>(
     array: &Array<'ctx>,
     offset: &BV<'ctx>,
@@ -148,11 +253,19 @@ mod tests {
     fn make_space<'ctx>(
         z3: &JingleContext<'ctx>,
         endianness: SleighEndianness,
+    ) -> ModeledSpace<'ctx> {
+        make_word_space(z3, endianness, 1)
+    }
+
+    fn make_word_space<'ctx>(
+        z3: &JingleContext<'ctx>,
+        endianness: SleighEndianness,
+        word_size_bytes: u32,
     ) -> ModeledSpace<'ctx> {
         let space_info = SpaceInfo {
             endianness,
             name: "ram".to_string(),
-            word_size_bytes: 1,
+            word_size_bytes,
             index_size_bytes: 4,
             index: 0,
             _type: SpaceType::IPTR_PROCESSOR,
@@ -261,4 +374,66 @@ mod tests {
     fn test_big_endian_read() {
         test_endian_read(SleighEndianness::Big)
     }
+
+    #[test]
+    fn test_word_addressed_space() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let mut space = make_word_space(&jingle, SleighEndianness::Little, 2);
+        // two words starting at word 1
+        space
+            .write_data(
+                &BV::from_u64(&z3, 0xdead_beef, 32),
+                &BV::from_u64(&z3, 1, 32),
+            )
+            .unwrap();
+        let read = |word: u64, size: usize| {
+            space
+                .read_data(&BV::from_u64(&z3, word, 32), size)
+                .unwrap()
+                .simplify()
+                .as_u64()
+        };
+        assert_eq!(read(1, 4), Some(0xdead_beef));
+        assert_eq!(read(1, 2), Some(0xbeef));
+        assert_eq!(read(2, 2), Some(0xdead));
+        // word 0 was never written
+        assert_eq!(read(0, 2), None);
+    }
+
+    #[test]
+    fn test_word_addressed_space_no_wrap() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let jingle = JingleContext::new(&z3, &sleigh);
+        let space_info = SpaceInfo {
+            endianness: SleighEndianness::Little,
+            name: "code".to_string(),
+            word_size_bytes: 2,
+            index_size_bytes: 2,
+            index: 0,
+            _type: SpaceType::IPTR_PROCESSOR,
+        };
+        let mut space = ModeledSpace::new(&jingle, &space_info);
+        for (word, val) in [(0u64, 0x1234u64), (0x8000, 0x5678), (0xffff, 0xbeef)] {
+            space
+                .write_data(&BV::from_u64(&z3, val, 16), &BV::from_u64(&z3, word, 16))
+                .unwrap();
+        }
+        let read = |word: u64| {
+            space
+                .read_data(&BV::from_u64(&z3, word, 16), 2)
+                .unwrap()
+                .simplify()
+                .as_u64()
+        };
+        assert_eq!(read(0), Some(0x1234));
+        assert_eq!(read(0x8000), Some(0x5678));
+        assert_eq!(read(0xffff), Some(0xbeef));
+    }
 }
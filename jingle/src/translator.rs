@@ -1,5 +1,5 @@
 use crate::error::JingleError;
-use jingle_sleigh::{Instruction, RegisterManager, SpaceInfo, VarNode};
+use jingle_sleigh::{Instruction, RegisterManager, SpaceInfo, UserOpManager, VarNode};
 
 use crate::modeling::ModeledInstruction;
 use crate::JingleContext;
@@ -74,3 +74,9 @@ impl RegisterManager for SleighTranslator<'_> {
         self.sleigh.get_registers()
     }
 }
+
+impl UserOpManager for SleighTranslator<'_> {
+    fn get_userop_names(&self) -> &[String] {
+        self.sleigh.get_userop_names()
+    }
+}
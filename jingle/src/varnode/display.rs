@@ -15,19 +15,23 @@ pub enum ResolvedVarNodeDisplay<'ctx> {
     Indirect(ResolvedIndirectVarNodeDisplay<'ctx>),
 }
 
+impl Display for ResolvedIndirectVarNodeDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "*({}[{}]:{})",
+            self.pointer_space_name,
+            self.pointer.simplify(),
+            self.access_size_bytes
+        )
+    }
+}
+
 impl Display for ResolvedVarNodeDisplay<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ResolvedVarNodeDisplay::Direct(d) => d.fmt(f),
-            ResolvedVarNodeDisplay::Indirect(i) => {
-                write!(
-                    f,
-                    "{}[{}]:{}",
-                    i.pointer_space_name,
-                    i.pointer.simplify(),
-                    i.access_size_bytes
-                )
-            }
+            ResolvedVarNodeDisplay::Indirect(i) => i.fmt(f),
         }
     }
 }
@@ -2,11 +2,12 @@ mod display;
 
 use crate::error::JingleError;
 use crate::error::JingleError::UnmodeledSpace;
-use crate::varnode::display::{ResolvedIndirectVarNodeDisplay, ResolvedVarNodeDisplay};
+pub use crate::varnode::display::{ResolvedIndirectVarNodeDisplay, ResolvedVarNodeDisplay};
 use jingle_sleigh::RegisterManager;
 use jingle_sleigh::VarNode;
 use std::hash::Hash;
 use z3::ast::BV;
+use z3::Context;
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct ResolvedIndirectVarNode<'ctx> {
@@ -16,6 +17,22 @@ pub struct ResolvedIndirectVarNode<'ctx> {
     pub access_size_bytes: usize,
 }
 
+impl<'ctx> ResolvedIndirectVarNode<'ctx> {
+    pub fn display<T: RegisterManager>(
+        &self,
+        ctx: &T,
+    ) -> Result<ResolvedIndirectVarNodeDisplay<'ctx>, JingleError> {
+        Ok(ResolvedIndirectVarNodeDisplay {
+            pointer_space_name: ctx
+                .get_space_info(self.pointer_space_idx)
+                .map(|o| o.name.clone())
+                .ok_or(UnmodeledSpace(self.pointer_space_idx))?,
+            pointer: self.pointer.clone(),
+            access_size_bytes: self.access_size_bytes,
+        })
+    }
+}
+
 /// This represents a general varnode that has been evaluated in a sequence of instructions.
 /// What distinguishes this from a regular VarNode is that, in the case of indirect varnodes,
 /// the pointer value has been already evaluated
@@ -25,23 +42,63 @@ pub enum ResolvedVarnode<'ctx> {
     Indirect(ResolvedIndirectVarNode<'ctx>),
 }
 
-impl ResolvedVarnode<'_> {
+impl<'ctx> ResolvedVarnode<'ctx> {
+    /// Move this varnode over to the z3 context `z3`
+    pub fn translate<'dest>(&self, z3: &'dest Context) -> ResolvedVarnode<'dest> {
+        match self {
+            ResolvedVarnode::Direct(d) => ResolvedVarnode::Direct(d.clone()),
+            ResolvedVarnode::Indirect(i) => ResolvedVarnode::Indirect(ResolvedIndirectVarNode {
+                pointer_space_idx: i.pointer_space_idx,
+                pointer: i.pointer.translate(z3),
+                pointer_location: i.pointer_location.clone(),
+                access_size_bytes: i.access_size_bytes,
+            }),
+        }
+    }
+
     pub fn display<T: RegisterManager>(
         &self,
         ctx: &T,
-    ) -> Result<ResolvedVarNodeDisplay, JingleError> {
+    ) -> Result<ResolvedVarNodeDisplay<'ctx>, JingleError> {
         match self {
             ResolvedVarnode::Direct(d) => Ok(ResolvedVarNodeDisplay::Direct(d.display(ctx)?)),
-            ResolvedVarnode::Indirect(i) => Ok(ResolvedVarNodeDisplay::Indirect(
-                ResolvedIndirectVarNodeDisplay {
-                    pointer_space_name: ctx
-                        .get_space_info(i.pointer_space_idx)
-                        .map(|o| o.name.clone())
-                        .ok_or(UnmodeledSpace)?,
-                    pointer: i.pointer.clone(),
-                    access_size_bytes: i.access_size_bytes,
-                },
-            )),
+            ResolvedVarnode::Indirect(i) => Ok(ResolvedVarNodeDisplay::Indirect(i.display(ctx)?)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::SLEIGH_ARCH;
+    use crate::varnode::{ResolvedIndirectVarNode, ResolvedVarnode};
+    use jingle_sleigh::context::SleighContextBuilder;
+    use jingle_sleigh::{varnode, SpaceManager};
+    use z3::ast::BV;
+    use z3::{Config, Context};
+
+    #[test]
+    fn test_resolved_display() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let z3 = Context::new(&Config::new());
+        let rax = varnode!(&sleigh, "register"[0]:8).unwrap();
+        let direct = ResolvedVarnode::Direct(rax.clone());
+        assert_eq!(direct.display(&sleigh).unwrap().to_string(), "RAX");
+        let indirect = ResolvedIndirectVarNode {
+            pointer_space_idx: sleigh.get_code_space_idx(),
+            pointer: BV::new_const(&z3, "ptr", 64),
+            pointer_location: rax,
+            access_size_bytes: 4,
+        };
+        assert_eq!(
+            indirect.display(&sleigh).unwrap().to_string(),
+            "*(ram[ptr]:4)"
+        );
+        let indirect = ResolvedVarnode::Indirect(indirect);
+        assert_eq!(
+            indirect.display(&sleigh).unwrap().to_string(),
+            "*(ram[ptr]:4)"
+        );
+    }
+}
@@ -34,6 +34,8 @@ pub struct LanguageDefinition {
     pub endian: SleighEndian,
     pub variant: String,
     pub version: String,
+    /// The size of the language's addresses, in bits
+    pub size: u32,
     #[serde(rename = "slafile")]
     pub sla_file: PathBuf,
     #[serde(rename = "processorspec")]
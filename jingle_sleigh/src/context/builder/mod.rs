@@ -1,8 +1,9 @@
-use crate::context::builder::language_def::{parse_ldef, LanguageDefinition};
+use crate::context::builder::language_def::{parse_ldef, LanguageDefinition, SleighEndian};
 use crate::context::builder::processor_spec::parse_pspec;
 use crate::context::SleighContext;
 use crate::error::JingleSleighError;
-use crate::error::JingleSleighError::{InvalidLanguageId, LanguageSpecRead};
+use crate::error::JingleSleighError::{InvalidLanguageId, LanguageSpecRead, SlaNotCompiled};
+use crate::space::SleighEndianness;
 use std::fmt::Debug;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -11,6 +12,34 @@ use tracing::{event, instrument, Level};
 pub(crate) mod language_def;
 pub(crate) mod processor_spec;
 
+/// Metadata about a `SLEIGH` language, as described by its `.ldefs` entry
+#[derive(Debug, Clone)]
+pub struct LanguageInfo {
+    pub id: String,
+    pub processor: String,
+    pub endianness: SleighEndianness,
+    /// The size of the language's addresses, in bits
+    pub size: u32,
+    pub variant: String,
+    pub description: String,
+}
+
+impl From<&LanguageDefinition> for LanguageInfo {
+    fn from(value: &LanguageDefinition) -> Self {
+        Self {
+            id: value.id.clone(),
+            processor: value.processor.clone(),
+            endianness: match value.endian {
+                SleighEndian::Little => SleighEndianness::Little,
+                SleighEndian::Big => SleighEndianness::Big,
+            },
+            size: value.size,
+            variant: value.variant.clone(),
+            description: value.description.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct SleighContextBuilder {
     defs: Vec<(LanguageDefinition, PathBuf)>,
@@ -21,12 +50,30 @@ impl SleighContextBuilder {
         self.defs.iter().map(|(l, _)| l.id.as_str()).collect()
     }
 
+    /// Returns the ids of all loaded languages containing `substring`, ignoring case.
+    pub fn find_languages(&self, substring: &str) -> Vec<&str> {
+        let substring = substring.to_lowercase();
+        self.get_language_ids()
+            .into_iter()
+            .filter(|id| id.to_lowercase().contains(&substring))
+            .collect()
+    }
+
+    /// Returns metadata describing the language with the given id, if it has been loaded.
+    pub fn language_info(&self, id: &str) -> Option<LanguageInfo> {
+        self.get_language(id).map(|(l, _)| l.into())
+    }
+
     fn get_language(&self, id: &str) -> Option<&(LanguageDefinition, PathBuf)> {
         self.defs.iter().find(|(p, _)| p.id.eq(id))
     }
     #[instrument(skip_all, fields(%id))]
     pub fn build(&self, id: &str) -> Result<SleighContext, JingleSleighError> {
         let (lang, path) = self.get_language(id).ok_or(InvalidLanguageId)?;
+        let sla_path = path.join(&lang.sla_file);
+        if !sla_path.exists() {
+            return Err(SlaNotCompiled(sla_path));
+        }
         let mut context = SleighContext::new(lang, path)?;
         event!(Level::INFO, "Created sleigh context");
         let pspec_path = path.join(&lang.processor_spec);
@@ -35,12 +82,12 @@ impl SleighContextBuilder {
             for set in ctx_sets.sets {
                 // todo: gross hack
                 if set.value.starts_with("0x") {
-                    context.set_initial_context(
+                    context.set_context_register(
                         &set.name,
                         u32::from_str_radix(&set.value[2..], 16).unwrap(),
                     )?;
                 } else {
-                    context.set_initial_context(&set.name, set.value.parse::<u32>().unwrap())?;
+                    context.set_context_register(&set.name, set.value.parse::<u32>().unwrap())?;
                 }
             }
         }
@@ -106,6 +153,7 @@ fn find_ldef(path: &Path) -> Result<Vec<PathBuf>, JingleSleighError> {
 mod tests {
     use crate::context::builder::processor_spec::parse_pspec;
     use crate::context::builder::{parse_ldef, SleighContextBuilder};
+    use crate::{JingleSleighError, SleighEndianness};
 
     use crate::tests::SLEIGH_ARCH;
     use std::path::Path;
@@ -150,4 +198,47 @@ mod tests {
         assert!(langs.get_language("sdf").is_none());
         assert!(langs.get_language(SLEIGH_ARCH).is_some());
     }
+
+    #[test]
+    fn test_language_info() {
+        let langs = SleighContextBuilder::load_folder(Path::new(
+            "ghidra/Ghidra/Processors/x86/data/languages/",
+        ))
+        .unwrap();
+        let info = langs.language_info(SLEIGH_ARCH).unwrap();
+        assert_eq!(info.id, SLEIGH_ARCH);
+        assert_eq!(info.processor, "x86");
+        assert!(matches!(info.endianness, SleighEndianness::Little));
+        assert_eq!(info.size, 64);
+        assert_eq!(info.variant, "default");
+        assert!(langs.language_info("sdf").is_none());
+    }
+
+    #[test]
+    fn test_find_languages() {
+        let langs = SleighContextBuilder::load_folder(Path::new(
+            "ghidra/Ghidra/Processors/x86/data/languages/",
+        ))
+        .unwrap();
+        let x86 = langs.find_languages("X86");
+        assert!(x86.contains(&SLEIGH_ARCH));
+        assert!(x86.iter().all(|id| id.starts_with("x86")));
+        assert_eq!(langs.find_languages(""), langs.get_language_ids());
+        assert!(langs.find_languages("ARM").is_empty());
+    }
+
+    #[test]
+    fn test_uncompiled_sla() {
+        // The ghidra source tree only contains the uncompiled .slaspec files
+        let langs = SleighContextBuilder::load_folder(Path::new(
+            "ghidra/Ghidra/Processors/x86/data/languages/",
+        ))
+        .unwrap();
+        match langs.build(SLEIGH_ARCH) {
+            Err(JingleSleighError::SlaNotCompiled(path)) => {
+                assert_eq!(path.extension().unwrap(), "sla")
+            }
+            _ => panic!("expected an uncompiled sla error"),
+        }
+    }
 }
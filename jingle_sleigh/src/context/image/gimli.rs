@@ -1,7 +1,14 @@
 use crate::context::image::{ImageProvider, ImageSection, ImageSectionIterator, Perms};
 use crate::{JingleSleighError, VarNode};
-use object::{Architecture, Endianness, File, Object, ObjectSection, Section, SectionKind};
+use object::elf::{SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE};
+use object::pe::{IMAGE_SCN_MEM_EXECUTE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE};
+use object::{
+    Architecture, BinaryFormat, Endianness, File, Object, ObjectSection, Section, SectionFlags,
+    SectionKind,
+};
 use std::cmp::{max, min};
+use std::ops::Range;
+use std::path::Path;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct OwnedSection {
@@ -91,6 +98,140 @@ impl ImageProvider for OwnedFile {
     }
 }
 
+/// An [ImageProvider] holding the code of an ELF file
+#[derive(Debug)]
+pub struct ElfImage {
+    file: OwnedFile,
+    entry_point: u64,
+    /// The name, address range, and permissions of every section that is mapped into memory
+    sections: Vec<(String, Range<u64>, Perms)>,
+}
+
+impl ElfImage {
+    /// Read the ELF file at `path`, returning an image of its code along with the id of the
+    /// `SLEIGH` language matching the architecture declared in its header.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(Self, String), JingleSleighError> {
+        let data = std::fs::read(path).map_err(|_| JingleSleighError::ImageLoadError)?;
+        let file = File::parse(data.as_slice()).map_err(|_| JingleSleighError::ImageLoadError)?;
+        if file.format() != BinaryFormat::Elf {
+            return Err(JingleSleighError::ImageLoadError);
+        }
+        let language_id =
+            map_gimli_architecture(&file).ok_or(JingleSleighError::UnknownImageArchitecture)?;
+        let sections = file
+            .sections()
+            .filter_map(|section| match section.flags() {
+                SectionFlags::Elf { sh_flags } if sh_flags & SHF_ALLOC as u64 != 0 => {
+                    let name = section.name().ok()?.to_string();
+                    let range = section.address()..section.address() + section.size();
+                    Some((name, range, map_elf_flags(sh_flags)))
+                }
+                _ => None,
+            })
+            .collect();
+        let image = Self {
+            file: OwnedFile::new(&file)?,
+            entry_point: file.entry(),
+            sections,
+        };
+        Ok((image, language_id.to_string()))
+    }
+
+    /// The entry point address declared in the ELF header
+    pub fn entry_point(&self) -> u64 {
+        self.entry_point
+    }
+
+    /// Iterate over the name, address range, and permissions of each section of the file that is
+    /// mapped into memory. Note that only code sections are available through [ImageProvider].
+    pub fn sections(&self) -> impl Iterator<Item = (&str, Range<u64>, Perms)> {
+        self.sections
+            .iter()
+            .map(|(name, range, perms)| (name.as_str(), range.clone(), perms.clone()))
+    }
+}
+
+impl ImageProvider for ElfImage {
+    fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
+        self.file.load(vn, output)
+    }
+
+    fn has_full_range(&self, vn: &VarNode) -> bool {
+        self.file.has_full_range(vn)
+    }
+
+    fn get_section_info(&self) -> ImageSectionIterator {
+        self.file.get_section_info()
+    }
+}
+
+/// An [ImageProvider] holding the sections of a PE file, each mapped at its virtual address
+/// (i.e. the image base plus the section's RVA)
+#[derive(Debug)]
+pub struct PeImage {
+    file: OwnedFile,
+    image_base: u64,
+}
+
+impl PeImage {
+    /// Read the PE file at `path`, returning an image of its sections along with the id of the
+    /// `SLEIGH` language matching the machine type declared in its header.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(Self, String), JingleSleighError> {
+        let data = std::fs::read(path).map_err(|_| JingleSleighError::ImageLoadError)?;
+        let file = File::parse(data.as_slice()).map_err(|_| JingleSleighError::ImageLoadError)?;
+        if file.format() != BinaryFormat::Pe {
+            return Err(JingleSleighError::ImageLoadError);
+        }
+        let language_id =
+            map_gimli_architecture(&file).ok_or(JingleSleighError::UnknownImageArchitecture)?;
+        let mut sections = vec![];
+        for section in file.sections() {
+            let SectionFlags::Coff { characteristics } = section.flags() else {
+                continue;
+            };
+            let mut data = section
+                .data()
+                .map_err(|_| JingleSleighError::ImageLoadError)?
+                .to_vec();
+            // The raw data of a section may be shorter than its virtual size; the remainder
+            // is zero-filled by the loader
+            if (data.len() as u64) < section.size() {
+                data.resize(section.size() as usize, 0);
+            }
+            sections.push(OwnedSection {
+                data,
+                perms: map_coff_characteristics(characteristics),
+                // object reports PE section addresses with the image base already applied
+                base_address: section.address() as usize,
+            });
+        }
+        let image = Self {
+            file: OwnedFile { sections },
+            image_base: file.relative_address_base(),
+        };
+        Ok((image, language_id.to_string()))
+    }
+
+    /// The preferred load address of the image, which section addresses already include.
+    pub fn image_base(&self) -> u64 {
+        self.image_base
+    }
+}
+
+impl ImageProvider for PeImage {
+    fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
+        self.file.load(vn, output)
+    }
+
+    fn has_full_range(&self, vn: &VarNode) -> bool {
+        self.file.has_full_range(vn)
+    }
+
+    fn get_section_info(&self) -> ImageSectionIterator {
+        self.file.get_section_info()
+    }
+}
+
 impl ImageProvider for File<'_> {
     fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
         let mut written = 0;
@@ -171,6 +312,22 @@ pub fn map_gimli_architecture(file: &File) -> Option<&'static str> {
     }
 }
 
+fn map_elf_flags(sh_flags: u64) -> Perms {
+    Perms {
+        read: true,
+        write: sh_flags & SHF_WRITE as u64 != 0,
+        exec: sh_flags & SHF_EXECINSTR as u64 != 0,
+    }
+}
+
+fn map_coff_characteristics(characteristics: u32) -> Perms {
+    Perms {
+        read: characteristics & IMAGE_SCN_MEM_READ != 0,
+        write: characteristics & IMAGE_SCN_MEM_WRITE != 0,
+        exec: characteristics & IMAGE_SCN_MEM_EXECUTE != 0,
+    }
+}
+
 fn map_sec_kind(kind: &SectionKind) -> Perms {
     match kind {
         SectionKind::Unknown => Perms::RWX,
@@ -183,3 +340,181 @@ fn map_sec_kind(kind: &SectionKind) -> Perms {
         _ => Perms::NONE,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::context::image::gimli::{ElfImage, PeImage};
+    use crate::context::image::{ImageProvider, Perms};
+    use crate::VarNode;
+    use std::path::PathBuf;
+
+    pub(crate) const TINY_ELF_ENTRY: u64 = 0x401000;
+    /// PUSH RBP; RET
+    const TINY_ELF_CODE: [u8; 2] = [0x55, 0xc3];
+
+    /// Builds a minimal x86-64 ELF executable containing a single `.text` section mapped at
+    /// [TINY_ELF_ENTRY], and writes it to a temporary file named `name`.
+    pub(crate) fn write_tiny_elf(name: &str) -> PathBuf {
+        let shstrtab = b"\0.text\0.shstrtab\0";
+        let text_offset = 64u64;
+        let shstrtab_offset = text_offset + TINY_ELF_CODE.len() as u64;
+        let shoff = (shstrtab_offset + shstrtab.len() as u64 + 7) & !7;
+        let mut elf = vec![0x7f, b'E', b'L', b'F', 2, 1, 1, 0];
+        elf.resize(16, 0);
+        elf.extend(2u16.to_le_bytes()); // ET_EXEC
+        elf.extend(62u16.to_le_bytes()); // EM_X86_64
+        elf.extend(1u32.to_le_bytes());
+        elf.extend(TINY_ELF_ENTRY.to_le_bytes());
+        elf.extend(0u64.to_le_bytes()); // no program headers
+        elf.extend(shoff.to_le_bytes());
+        elf.extend(0u32.to_le_bytes());
+        for half in [64u16, 56, 0, 64, 3, 2] {
+            elf.extend(half.to_le_bytes());
+        }
+        elf.extend(TINY_ELF_CODE);
+        elf.extend(shstrtab);
+        elf.resize(shoff as usize, 0);
+        // (name, type, flags, addr, offset, size, align)
+        let headers = [
+            (0u32, 0u32, 0u64, 0u64, 0u64, 0u64, 0u64),
+            (
+                1,
+                1, // SHT_PROGBITS
+                6, // SHF_ALLOC | SHF_EXECINSTR
+                TINY_ELF_ENTRY,
+                text_offset,
+                TINY_ELF_CODE.len() as u64,
+                16,
+            ),
+            (
+                7,
+                3, // SHT_STRTAB
+                0,
+                0,
+                shstrtab_offset,
+                shstrtab.len() as u64,
+                1,
+            ),
+        ];
+        for (name, ty, flags, addr, offset, size, align) in headers {
+            elf.extend(name.to_le_bytes());
+            elf.extend(ty.to_le_bytes());
+            elf.extend(flags.to_le_bytes());
+            elf.extend(addr.to_le_bytes());
+            elf.extend(offset.to_le_bytes());
+            elf.extend(size.to_le_bytes());
+            elf.extend(0u32.to_le_bytes());
+            elf.extend(0u32.to_le_bytes());
+            elf.extend(align.to_le_bytes());
+            elf.extend(0u64.to_le_bytes());
+        }
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, elf).unwrap();
+        path
+    }
+
+    const TINY_PE_IMAGE_BASE: u64 = 0x1_4000_0000;
+    const TINY_PE_TEXT_RVA: u32 = 0x1000;
+
+    /// Builds a minimal x86-64 PE32+ image containing a single `.text` section at
+    /// [TINY_PE_TEXT_RVA], and writes it to a temporary file named `name`.
+    fn write_tiny_pe(name: &str) -> PathBuf {
+        let file_alignment = 0x200u32;
+        let mut pe = vec![b'M', b'Z'];
+        pe.resize(0x3c, 0);
+        pe.extend(0x40u32.to_le_bytes()); // e_lfanew
+        pe.extend(b"PE\0\0");
+        // COFF header
+        pe.extend(0x8664u16.to_le_bytes()); // IMAGE_FILE_MACHINE_AMD64
+        pe.extend(1u16.to_le_bytes());
+        pe.extend([0u8; 12]);
+        pe.extend(240u16.to_le_bytes()); // SizeOfOptionalHeader
+        pe.extend(0x22u16.to_le_bytes());
+        // PE32+ optional header
+        pe.extend(0x20bu16.to_le_bytes());
+        pe.extend([0u8; 2]);
+        for field in [file_alignment, 0, 0, TINY_PE_TEXT_RVA, TINY_PE_TEXT_RVA] {
+            pe.extend(field.to_le_bytes());
+        }
+        pe.extend(TINY_PE_IMAGE_BASE.to_le_bytes());
+        pe.extend(0x1000u32.to_le_bytes()); // SectionAlignment
+        pe.extend(file_alignment.to_le_bytes());
+        pe.extend([0u8; 12]); // versions
+        pe.extend(0u32.to_le_bytes());
+        pe.extend(0x2000u32.to_le_bytes()); // SizeOfImage
+        pe.extend(file_alignment.to_le_bytes()); // SizeOfHeaders
+        pe.extend(0u32.to_le_bytes());
+        pe.extend(3u16.to_le_bytes()); // IMAGE_SUBSYSTEM_WINDOWS_CUI
+        pe.extend(0u16.to_le_bytes());
+        pe.extend([0u8; 32]); // stack and heap sizes
+        pe.extend(0u32.to_le_bytes());
+        pe.extend(16u32.to_le_bytes()); // NumberOfRvaAndSizes
+        pe.extend([0u8; 128]);
+        // section table
+        pe.extend(b".text\0\0\0");
+        pe.extend((TINY_ELF_CODE.len() as u32).to_le_bytes());
+        pe.extend(TINY_PE_TEXT_RVA.to_le_bytes());
+        pe.extend(file_alignment.to_le_bytes()); // SizeOfRawData
+        pe.extend(file_alignment.to_le_bytes()); // PointerToRawData
+        pe.extend([0u8; 12]);
+        pe.extend(0x6000_0020u32.to_le_bytes()); // CODE | EXECUTE | READ
+        pe.resize(file_alignment as usize, 0);
+        pe.extend(TINY_ELF_CODE);
+        pe.resize(2 * file_alignment as usize, 0);
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, pe).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_pe_image_open() {
+        let path = write_tiny_pe("jingle_test_pe_image_open");
+        let (image, language_id) = PeImage::open(&path).unwrap();
+        assert_eq!(language_id, "x86:LE:64:default");
+        assert_eq!(image.image_base(), TINY_PE_IMAGE_BASE);
+        let text = VarNode {
+            space_index: 0,
+            offset: image.image_base() + TINY_PE_TEXT_RVA as u64,
+            size: TINY_ELF_CODE.len(),
+        };
+        assert_eq!(image.get_bytes(&text), Some(TINY_ELF_CODE.to_vec()));
+        let section = image.get_section_info().next().unwrap();
+        assert_eq!(section.perms, Perms::RX);
+        assert_eq!(section.base_address as u64, text.offset);
+        let elf = write_tiny_elf("jingle_test_pe_image_open_elf");
+        assert!(PeImage::open(elf).is_err());
+    }
+
+    #[test]
+    fn test_elf_image_open() {
+        let path = write_tiny_elf("jingle_test_elf_image_open");
+        let (image, language_id) = ElfImage::open(&path).unwrap();
+        assert_eq!(language_id, "x86:LE:64:default");
+        let text = VarNode {
+            space_index: 0,
+            offset: TINY_ELF_ENTRY,
+            size: TINY_ELF_CODE.len(),
+        };
+        assert!(image.has_full_range(&text));
+        assert_eq!(image.get_bytes(&text), Some(TINY_ELF_CODE.to_vec()));
+        let section = image.get_section_info().next().unwrap();
+        assert_eq!(section.perms, Perms::RX);
+        assert!(ElfImage::open(path.with_extension("missing")).is_err());
+    }
+
+    #[test]
+    fn test_elf_image_sections() {
+        let path = write_tiny_elf("jingle_test_elf_image_sections");
+        let (image, _) = ElfImage::open(&path).unwrap();
+        assert_eq!(image.entry_point(), TINY_ELF_ENTRY);
+        let sections: Vec<_> = image.sections().collect();
+        assert_eq!(
+            sections,
+            vec![(
+                ".text",
+                TINY_ELF_ENTRY..TINY_ELF_ENTRY + TINY_ELF_CODE.len() as u64,
+                Perms::RX
+            )]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::VarNode;
 use std::cmp::min;
+use std::collections::BTreeMap;
 use std::iter::once;
 use std::ops::Range;
 
@@ -105,6 +106,120 @@ impl ImageProvider for Vec<u8> {
     }
 }
 
+/// Raw bytes that are located at `base`, rather than at address 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasedImage {
+    pub base: u64,
+    pub data: Vec<u8>,
+}
+
+impl BasedImage {
+    pub fn new(base: u64, data: Vec<u8>) -> Self {
+        Self { base, data }
+    }
+
+    /// Translate a varnode into one relative to the start of [data](Self::data), if it
+    /// does not start before [base](Self::base)
+    fn relative(&self, vn: &VarNode) -> Option<VarNode> {
+        let offset = vn.offset.checked_sub(self.base)?;
+        Some(VarNode {
+            space_index: vn.space_index,
+            offset,
+            size: vn.size,
+        })
+    }
+}
+
+impl ImageProvider for BasedImage {
+    fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
+        match self.relative(vn) {
+            Some(vn) => self.data.load(&vn, output),
+            None => {
+                output.fill(0);
+                0
+            }
+        }
+    }
+
+    fn has_full_range(&self, vn: &VarNode) -> bool {
+        self.relative(vn)
+            .is_some_and(|vn| self.data.has_full_range(&vn))
+    }
+
+    fn get_section_info(&self) -> ImageSectionIterator {
+        ImageSectionIterator::new(once(ImageSection {
+            data: &self.data,
+            base_address: self.base as usize,
+            perms: Perms::RX,
+        }))
+    }
+}
+
+/// An image that overlays byte patches on top of another [ImageProvider], without modifying it.
+/// Useful for modeling self-modifying code.
+///
+/// Patches are applied in order of address, so where two patches overlap, the bytes of the
+/// patch with the higher address take precedence.
+#[derive(Debug, Clone)]
+pub struct OverlayImage<P: ImageProvider> {
+    pub base: P,
+    pub patches: BTreeMap<u64, Vec<u8>>,
+}
+
+impl<P: ImageProvider> OverlayImage<P> {
+    pub fn new(base: P) -> Self {
+        Self {
+            base,
+            patches: Default::default(),
+        }
+    }
+
+    /// Overwrite the bytes starting at `addr` with `bytes`, replacing any patch previously made
+    /// at the same address
+    pub fn patch(&mut self, addr: u64, bytes: &[u8]) {
+        self.patches.insert(addr, bytes.to_vec());
+    }
+
+    /// Copy the patched bytes falling in `vn` into `output`, marking which bytes were written
+    fn apply_patches(&self, vn: &VarNode, output: &mut [u8], covered: &mut [bool]) {
+        let end = vn.offset + vn.size as u64;
+        for (addr, bytes) in self.patches.range(..end) {
+            for (i, byte) in bytes.iter().enumerate() {
+                let Some(o) = (addr + i as u64).checked_sub(vn.offset) else {
+                    continue;
+                };
+                if let Some(out) = output.get_mut(o as usize) {
+                    *out = *byte;
+                    covered[o as usize] = true;
+                }
+            }
+        }
+    }
+}
+
+impl<P: ImageProvider> ImageProvider for OverlayImage<P> {
+    fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
+        let loaded = self.base.load(vn, output);
+        let mut covered: Vec<bool> = (0..output.len()).map(|i| i < loaded).collect();
+        self.apply_patches(vn, output, &mut covered);
+        covered.iter().take_while(|c| **c).count()
+    }
+
+    fn has_full_range(&self, vn: &VarNode) -> bool {
+        if self.base.has_full_range(vn) {
+            return true;
+        }
+        let mut output = vec![0u8; vn.size];
+        vn.size > 0 && self.load(vn, &mut output) == vn.size
+    }
+
+    /// The sections of the underlying image. Patches falling outside of these sections are not
+    /// reported.
+    fn get_section_info(&self) -> ImageSectionIterator {
+        self.base.get_section_info()
+    }
+}
+
 impl<T: ImageProvider> ImageProvider for &T {
     fn load(&self, vn: &VarNode, output: &mut [u8]) -> usize {
         (*self).load(vn, output)
@@ -165,7 +280,10 @@ pub struct ImageSection<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::context::image::{ImageProvider, ImageSection};
+    use crate::context::image::{BasedImage, ImageProvider, ImageSection, OverlayImage};
+    use crate::context::SleighContextBuilder;
+    use crate::tests::SLEIGH_ARCH;
+    use crate::VarNode;
 
     #[test]
     fn test_vec_sections() {
@@ -173,4 +291,71 @@ mod tests {
         let sections: Vec<ImageSection> = data.get_section_info().collect();
         assert_ne!(sections, vec![])
     }
+
+    #[test]
+    fn test_based_image() {
+        let base = 0x1_4000_1000;
+        let image = BasedImage::new(base, vec![0x55, 0x48, 0x89, 0xe5, 0xc3]);
+        let vn = VarNode {
+            space_index: 0,
+            offset: base + 1,
+            size: 3,
+        };
+        assert!(image.has_full_range(&vn));
+        assert_eq!(image.get_bytes(&vn), Some(vec![0x48, 0x89, 0xe5]));
+        let before = VarNode {
+            offset: base - 1,
+            ..vn.clone()
+        };
+        assert!(!image.has_full_range(&before));
+        assert_eq!(image.get_bytes(&before), None);
+        let past = VarNode {
+            offset: base + 3,
+            ..vn
+        };
+        assert!(!image.has_full_range(&past));
+        let section = image.get_section_info().next().unwrap();
+        assert_eq!(section.base_address as u64, base);
+    }
+
+    #[test]
+    fn test_overlay_image() {
+        let data: [u8; 4] = [1, 2, 3, 4];
+        let mut image = OverlayImage::new(data.as_slice());
+        image.patch(0, &[7, 7]);
+        // partially overlaps the end of the underlying image
+        image.patch(2, &[9, 9, 9]);
+        let vn = |offset, size| VarNode {
+            space_index: 0,
+            offset,
+            size,
+        };
+        assert_eq!(image.get_bytes(&vn(1, 4)), Some(vec![7, 9, 9, 9]));
+        assert!(image.has_full_range(&vn(0, 5)));
+        assert!(!image.has_full_range(&vn(0, 6)));
+        assert_eq!(image.get_bytes(&vn(0, 6)), None);
+        assert_eq!(data, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_overlay_image_decode() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // NOP, patched to RET
+        let data: [u8; 1] = [0x90];
+        let mut image = OverlayImage::new(data.as_slice());
+        image.patch(0, &[0xc3]);
+        let loaded = sleigh.initialize_with_image(data.as_slice()).unwrap();
+        assert_eq!(
+            loaded.instruction_at(0).unwrap().disassembly.mnemonic,
+            "NOP"
+        );
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let patched = sleigh.initialize_with_image(image).unwrap();
+        assert_eq!(
+            patched.instruction_at(0).unwrap().disassembly.mnemonic,
+            "RET"
+        );
+    }
 }
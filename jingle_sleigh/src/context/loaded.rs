@@ -3,7 +3,12 @@ use crate::context::instruction_iterator::SleighContextInstructionIterator;
 use crate::context::SleighContext;
 use crate::ffi::context_ffi::ImageFFI;
 use crate::JingleSleighError::ImageLoadError;
-use crate::{Instruction, JingleSleighError, RegisterManager, SpaceInfo, SpaceManager, VarNode};
+use crate::{
+    DecodeError, Instruction, JingleSleighError, PcodeOperation, RegisterManager, SpaceInfo,
+    SpaceManager, UserOpManager, VarNode,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
@@ -17,6 +22,11 @@ pub struct LoadedSleighContext<'a> {
     sleigh: SleighContext,
     /// A handle to the image source being queried by the [SleighContext].
     img: Pin<Box<ImageFFI<'a>>>,
+    /// Instructions that have already been lifted, keyed by their address. This assumes the image
+    /// does not change underneath us; it is cleared whenever the image or its base address is
+    /// changed through this context, and can be cleared manually with
+    /// [`clear_cache`](Self::clear_cache).
+    instruction_cache: RefCell<HashMap<u64, Instruction>>,
 }
 
 impl Debug for LoadedSleighContext<'_> {
@@ -50,6 +60,7 @@ impl<'a> LoadedSleighContext<'a> {
         let mut s = Self {
             sleigh: sleigh_context,
             img,
+            instruction_cache: Default::default(),
         };
         let (ctx, img) = s.borrow_parts();
         ctx.ctx
@@ -60,25 +71,135 @@ impl<'a> LoadedSleighContext<'a> {
     }
     /// Query `sleigh` for the instruction associated with the given offset in the default code
     /// space.
+    ///
+    /// Lifted instructions are cached, so repeated queries of the same offset do not go back to
+    /// `sleigh`. The cache assumes the underlying image is immutable; if the bytes behind an
+    /// [`ImageProvider`] can change, call [`clear_cache`](Self::clear_cache) afterward.
     /// todo: consider using a varnode instead of a raw offset.
     pub fn instruction_at(&self, offset: u64) -> Option<Instruction> {
+        self.try_instruction_at(offset).ok()
+    }
+
+    /// Like [instruction_at](Self::instruction_at), but reports why no instruction could be
+    /// read at the given offset.
+    pub fn try_instruction_at(&self, offset: u64) -> Result<Instruction, DecodeError> {
+        if let Some(instr) = self.instruction_cache.borrow().get(&offset) {
+            return Ok(instr.clone());
+        }
+        let instr = self.decode(offset)?;
+        self.instruction_cache
+            .borrow_mut()
+            .insert(offset, instr.clone());
+        Ok(instr)
+    }
+
+    /// Like [instruction_at](Self::instruction_at), but decodes with the given context variables
+    /// (e.g. `[("TMode", 1)]` to decode ARM code as Thumb) applied for this decode only. The
+    /// previous values of those variables are restored before returning, so subsequent decodes
+    /// are unaffected. The result bypasses the instruction cache.
+    pub fn instruction_at_with_context(
+        &mut self,
+        offset: u64,
+        context: &[(&str, u32)],
+    ) -> Result<Option<Instruction>, JingleSleighError> {
+        let previous = context
+            .iter()
+            .map(|(name, _)| Ok((*name, self.sleigh.context_register(name)?)))
+            .collect::<Result<Vec<_>, JingleSleighError>>()?;
+        self.apply_context(context)?;
+        let instr = self.decode(offset).ok();
+        self.apply_context(&previous)?;
+        Ok(instr)
+    }
+
+    /// Lift the instruction at the given offset without consulting the instruction cache.
+    fn decode(&self, offset: u64) -> Result<Instruction, DecodeError> {
+        let space_index = self.sleigh.get_code_space_idx();
+        let first_byte = VarNode {
+            space_index,
+            size: 1,
+            offset,
+        };
+        if !self.img.has_range(&first_byte) {
+            return Err(DecodeError::OutOfBounds);
+        }
         let instr = self
             .ctx
             .get_one_instruction(offset)
             .map(Instruction::from)
-            .ok()?;
+            .map_err(|_| DecodeError::UnknownInstruction)?;
         let vn = VarNode {
-            space_index: self.sleigh.get_code_space_idx(),
+            space_index,
             size: instr.length,
             offset,
         };
         if self.img.has_range(&vn) {
-            Some(instr)
+            Ok(instr)
         } else {
-            None
+            Err(DecodeError::IncompleteBytes)
         }
     }
 
+    /// Query `sleigh` for the length in bytes of the instruction at the given offset in the default
+    /// code space, without lifting its `p-code`. Useful for quickly sweeping over code.
+    ///
+    /// Unlike [Instruction::length], the result does not include any delay slots. For that
+    /// reason, this never consults the instruction cache.
+    pub fn instruction_length_at(&self, offset: u64) -> Option<usize> {
+        let length = self.ctx.get_instruction_length(offset).ok()?;
+        let vn = VarNode {
+            space_index: self.sleigh.get_code_space_idx(),
+            size: length,
+            offset,
+        };
+        self.img.has_range(&vn).then_some(length)
+    }
+
+    /// Linearly disassemble the [`Instruction`]s in `[start, end)` of the default code space,
+    /// stopping early at the first address that fails to decode. An instruction that would
+    /// extend past `end` is not included.
+    pub fn disassemble_range(&self, start: u64, end: u64) -> Vec<Instruction> {
+        let mut instructions = vec![];
+        let mut offset = start;
+        while offset < end {
+            match self.instruction_at(offset) {
+                Some(instr) if instr.next_addr() <= end => {
+                    offset = instr.next_addr();
+                    instructions.push(instr);
+                }
+                _ => break,
+            }
+        }
+        instructions
+    }
+
+    /// Drop all cached [`Instruction`]s, forcing them to be lifted again on next access.
+    pub fn clear_cache(&self) {
+        self.instruction_cache.borrow_mut().clear();
+    }
+
+    /// Eagerly lift up to `count` [`Instruction`]s starting at `start`, following fallthrough,
+    /// so that later calls to [`instruction_at`](Self::instruction_at) for those addresses are
+    /// served from the cache. Stops early at an instruction that cannot fall through (e.g. an
+    /// unconditional branch or a return) or at an address that fails to decode.
+    ///
+    /// Returns the number of instructions that were lifted.
+    pub fn prefetch(&self, start: u64, count: usize) -> usize {
+        let mut offset = start;
+        for fetched in 0..count {
+            match self.instruction_at(offset) {
+                Some(instr) => {
+                    if !self.has_fallthrough(&instr) {
+                        return fetched + 1;
+                    }
+                    offset = instr.next_addr();
+                }
+                None => return fetched,
+            }
+        }
+        count
+    }
+
     /// Read an iterator of at most `max_instrs` [`Instruction`]s from `offset` in the default code
     /// space.
     /// todo: consider using a varnode instead of a raw offset
@@ -112,6 +233,7 @@ impl<'a> LoadedSleighContext<'a> {
         &mut self,
         img: T,
     ) -> Result<(), JingleSleighError> {
+        self.clear_cache();
         let (sleigh, img_ref) = self.borrow_parts();
         *img_ref = ImageFFI::new(img, sleigh.get_code_space_idx());
         sleigh
@@ -121,6 +243,32 @@ impl<'a> LoadedSleighContext<'a> {
             .map_err(|_| ImageLoadError)
     }
 
+    /// Sets the default value of the context variable `name` (e.g. ARM's `TMode`) and
+    /// re-initializes `sleigh` so that subsequent decoding observes it. See
+    /// [SleighContext::set_context_register] for where context variable names come from.
+    pub fn set_context_register(
+        &mut self,
+        name: &str,
+        value: u32,
+    ) -> Result<(), JingleSleighError> {
+        self.clear_cache();
+        self.apply_context(&[(name, value)])
+    }
+
+    /// Set the given context variables and re-initialize `sleigh`, discarding its own
+    /// cache of decoded instructions.
+    fn apply_context(&mut self, context: &[(&str, u32)]) -> Result<(), JingleSleighError> {
+        let (sleigh, img) = self.borrow_parts();
+        for (name, value) in context {
+            sleigh.set_context_register(name, *value)?;
+        }
+        sleigh
+            .ctx
+            .pin_mut()
+            .setImage(img)
+            .map_err(|_| ImageLoadError)
+    }
+
     /// Returns an iterator of entries describing the sections of the configured image provider.
     pub fn get_sections(&self) -> impl Iterator<Item = ImageSection> {
         self.img.provider.get_section_info().map(|mut s| {
@@ -135,6 +283,7 @@ impl<'a> LoadedSleighContext<'a> {
 
     /// Rebase the loaded image to `offset`
     pub fn set_base_address(&mut self, offset: u64) {
+        self.clear_cache();
         self.img.set_base_address(offset);
     }
 
@@ -143,6 +292,16 @@ impl<'a> LoadedSleighContext<'a> {
         self.img.get_base_address()
     }
 
+    /// Whether control can continue to [`Instruction::next_addr`] after this instruction.
+    /// Branches into the `const` space are `p-code`-relative and stay within the instruction.
+    fn has_fallthrough(&self, instr: &Instruction) -> bool {
+        match instr.ops.last() {
+            Some(PcodeOperation::Branch { input }) => self.is_const_space(input.space_index),
+            Some(PcodeOperation::BranchInd { .. }) | Some(PcodeOperation::Return { .. }) => false,
+            _ => true,
+        }
+    }
+
     // todo: properly account for spaces with non-byte-based indexing
     fn adjust_varnode_vma(&self, vn: &VarNode) -> VarNode {
         VarNode {
@@ -181,12 +340,18 @@ impl RegisterManager for LoadedSleighContext<'_> {
     }
 }
 
+impl UserOpManager for LoadedSleighContext<'_> {
+    fn get_userop_names(&self) -> &[String] {
+        self.sleigh.get_userop_names()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::context::SleighContextBuilder;
     use crate::tests::SLEIGH_ARCH;
     use crate::PcodeOperation::Branch;
-    use crate::VarNode;
+    use crate::{DecodeError, Instruction, VarNode};
 
     #[test]
     fn test_adjust_vma() {
@@ -230,6 +395,148 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_instruction_length() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV EAX, 0; NOP; RET; truncated MOV
+        let img: [u8; 9] = [0xb8, 0x00, 0x00, 0x00, 0x00, 0x90, 0xc3, 0xb8, 0x00];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        for offset in [0, 5, 6] {
+            let length = loaded.instruction_length_at(offset);
+            assert_eq!(length, loaded.instruction_at(offset).map(|i| i.length));
+            assert!(length.is_some());
+        }
+        assert_eq!(loaded.instruction_length_at(7), None);
+
+        let sleigh = ctx_builder.build("MIPS:BE:32:default").unwrap();
+        // b 0x10; addiu v0, zero, 1 (delay slot)
+        let img: [u8; 8] = [0x10, 0x00, 0x00, 0x03, 0x24, 0x02, 0x00, 0x01];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        // the result must not depend on whether the instruction has been decoded already
+        assert_eq!(loaded.instruction_length_at(0), Some(4));
+        assert_eq!(loaded.instruction_at(0).map(|i| i.length), Some(8));
+        assert_eq!(loaded.instruction_length_at(0), Some(4));
+    }
+
+    #[test]
+    fn test_disassemble_range() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // NOP; NOP; MOV EAX, 0; RET
+        let img: [u8; 8] = [0x90, 0x90, 0xb8, 0x00, 0x00, 0x00, 0x00, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let addrs =
+            |instrs: Vec<Instruction>| -> Vec<u64> { instrs.iter().map(|i| i.address).collect() };
+        assert_eq!(addrs(loaded.disassemble_range(0, 8)), vec![0, 1, 2, 7]);
+        assert_eq!(addrs(loaded.disassemble_range(0, 5)), vec![0, 1]);
+        assert_eq!(addrs(loaded.disassemble_range(2, 20)), vec![2, 7]);
+    }
+
+    #[test]
+    fn test_instruction_cache() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // NOP; RET
+        let img: [u8; 2] = [0x90, 0xc3];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let nop = loaded.instruction_at(0).unwrap();
+        assert_eq!(loaded.instruction_cache.borrow().get(&0), Some(&nop));
+        // Plant a bogus entry: if it comes back, the lookup never reached sleigh
+        let mut bogus = nop.clone();
+        bogus.ops.clear();
+        loaded
+            .instruction_cache
+            .borrow_mut()
+            .insert(0, bogus.clone());
+        assert_eq!(loaded.instruction_at(0), Some(bogus));
+        loaded.clear_cache();
+        assert!(loaded.instruction_cache.borrow().is_empty());
+        assert_eq!(loaded.instruction_at(0), Some(nop));
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // NOP; NOP; RET; NOP
+        let img: [u8; 4] = [0x90, 0x90, 0xc3, 0x90];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        assert_eq!(loaded.prefetch(0, 10), 3);
+        for addr in 0..3 {
+            assert!(loaded.instruction_cache.borrow().contains_key(&addr));
+        }
+        assert!(!loaded.instruction_cache.borrow().contains_key(&3));
+        let cached = loaded.instruction_cache.borrow().get(&2).cloned();
+        assert_eq!(loaded.instruction_at(2), cached);
+    }
+
+    #[test]
+    fn test_set_context_register() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV RAX, RAX in long mode; DEC EAX followed by MOV EAX, EAX otherwise
+        let img: [u8; 3] = [0x48, 0x89, 0xc0];
+        let mut loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let long = loaded.instruction_at(0).unwrap();
+        assert_eq!(long.length, 3);
+        assert_eq!(long.disassembly.mnemonic, "MOV");
+        loaded.set_context_register("longMode", 0).unwrap();
+        loaded.set_context_register("addrsize", 1).unwrap();
+        let legacy = loaded.instruction_at(0).unwrap();
+        assert_eq!(legacy.length, 1);
+        assert_eq!(legacy.disassembly.mnemonic, "DEC");
+    }
+
+    #[test]
+    fn test_instruction_at_with_context() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build("ARM:LE:32:v8").unwrap();
+        // MOV r0, r0 as ARM; two LSLS r0, r0, #0 as Thumb
+        let img: [u8; 4] = [0x00, 0x00, 0xa0, 0xe1];
+        let mut loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let arm = loaded.instruction_at(0).unwrap();
+        assert_eq!(arm.length, 4);
+        let thumb = loaded
+            .instruction_at_with_context(0, &[("TMode", 1)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(thumb.length, 2);
+        assert_eq!(loaded.context_register("TMode").unwrap(), 0);
+        loaded.clear_cache();
+        assert_eq!(loaded.instruction_at(0), Some(arm));
+        assert!(loaded
+            .instruction_at_with_context(0, &[("fake", 1)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_try_instruction_at() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // NOP; PUSH ES (invalid in 64-bit mode); the first two bytes of MOV [RAX], RAX
+        let img: [u8; 4] = [0x90, 0x06, 0x48, 0x89];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        assert_eq!(loaded.try_instruction_at(0).unwrap().length, 1);
+        assert_eq!(
+            loaded.try_instruction_at(1),
+            Err(DecodeError::UnknownInstruction)
+        );
+        assert_eq!(
+            loaded.try_instruction_at(2),
+            Err(DecodeError::IncompleteBytes)
+        );
+        assert_eq!(loaded.try_instruction_at(4), Err(DecodeError::OutOfBounds));
+        assert_eq!(loaded.instruction_at(2), None);
+    }
+
     #[test]
     pub fn relative_addresses() {
         let ctx_builder =
@@ -7,14 +7,14 @@ use crate::error::JingleSleighError;
 use crate::error::JingleSleighError::{LanguageSpecRead, SleighInitError};
 use crate::ffi::addrspace::bridge::AddrSpaceHandle;
 use crate::ffi::context_ffi::bridge::ContextFFI;
-use crate::space::{RegisterManager, SpaceInfo, SpaceManager};
-pub use builder::SleighContextBuilder;
+use crate::space::{RegisterManager, SpaceInfo, SpaceManager, UserOpManager};
+pub use builder::{LanguageInfo, SleighContextBuilder};
 
 use crate::context::builder::language_def::LanguageDefinition;
 use crate::context::image::ImageProvider;
 use crate::context::loaded::LoadedSleighContext;
 use crate::ffi::context_ffi::CTX_BUILD_MUTEX;
-use crate::JingleSleighError::{ImageLoadError, SleighCompilerMutexError};
+use crate::JingleSleighError::{InvalidContextVariable, SleighCompilerMutexError};
 use crate::VarNode;
 use cxx::{SharedPtr, UniquePtr};
 use std::fmt::{Debug, Formatter};
@@ -25,6 +25,7 @@ pub struct SleighContext {
     spaces: Vec<SpaceInfo>,
     language_id: String,
     registers: Vec<(VarNode, String)>,
+    userops: Vec<String>,
 }
 
 impl Debug for SleighContext {
@@ -71,6 +72,12 @@ impl RegisterManager for SleighContext {
     }
 }
 
+impl UserOpManager for SleighContext {
+    fn get_userop_names(&self) -> &[String] {
+        self.userops.as_slice()
+    }
+}
+
 impl SleighContext {
     pub(crate) fn new<T: AsRef<Path>>(
         language_def: &LanguageDefinition,
@@ -91,19 +98,31 @@ impl SleighContext {
                     .iter()
                     .map(|b| (VarNode::from(&b.varnode), b.name.clone()))
                     .collect();
+                let userops = ctx.getUserOpNames();
 
                 Ok(Self {
                     ctx,
                     spaces,
                     language_id: language_def.id.clone(),
                     registers,
+                    userops,
                 })
             }
             Err(_) => Err(SleighCompilerMutexError),
         }
     }
 
-    pub(crate) fn set_initial_context(
+    /// Sets the default value of the context variable `name` for all subsequent decoding.
+    ///
+    /// Context variables are declared in the `define context` block of an architecture's
+    /// `.slaspec`, and their initial values come from the `<context_data>` section of its
+    /// `.pspec`, which [SleighContextBuilder::build] applies automatically. Common examples
+    /// are `longMode`, `addrsize` and `opsize` on x86 and `TMode` (Thumb) on ARM.
+    ///
+    /// If this context has already been loaded with an image, use
+    /// [LoadedSleighContext::set_context_register] instead, so that previously decoded
+    /// instructions are discarded.
+    pub fn set_context_register(
         &mut self,
         name: &str,
         value: u32,
@@ -111,7 +130,14 @@ impl SleighContext {
         self.ctx
             .pin_mut()
             .set_initial_context(name, value)
-            .map_err(|_| ImageLoadError)
+            .map_err(|_| InvalidContextVariable(name.to_string()))
+    }
+
+    /// Returns the default value of the context variable `name`, as currently used for decoding.
+    pub fn context_register(&self, name: &str) -> Result<u32, JingleSleighError> {
+        self.ctx
+            .get_context_default(name)
+            .map_err(|_| InvalidContextVariable(name.to_string()))
     }
 
     pub fn spaces(&self) -> Vec<SharedPtr<AddrSpaceHandle>> {
@@ -138,7 +164,10 @@ impl SleighContext {
 mod test {
     use crate::context::SleighContextBuilder;
     use crate::tests::SLEIGH_ARCH;
-    use crate::{RegisterManager, VarNode};
+    use crate::{
+        varnode, JingleSleighError, RegisterManager, SleighEndianness, SpaceManager, SpaceType,
+        UserOpManager, VarNode,
+    };
 
     #[test]
     fn get_regs() {
@@ -198,4 +227,68 @@ mod test {
             None
         );
     }
+
+    #[test]
+    fn get_unique_space() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let idx = sleigh.get_unique_space_idx().unwrap();
+        let info = sleigh.get_space_info(idx).unwrap();
+        assert_eq!(info._type, SpaceType::IPTR_INTERNAL);
+        assert_eq!(info.name, "unique");
+    }
+
+    #[test]
+    fn get_const_varnode() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let vn = sleigh.const_varnode(0x1234, 4).unwrap();
+        assert!(sleigh.is_const_space(vn.space_index));
+        assert_eq!(vn.offset, 0x1234);
+        assert_eq!(vn.size, 4);
+        assert_eq!(vn, varnode!(&sleigh, #0x1234:4).unwrap());
+        assert!(!sleigh.is_const_space(sleigh.get_code_space_idx()));
+    }
+
+    #[test]
+    fn get_word_addressed_space() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        // AVR8 addresses its code space in 2-byte words
+        let sleigh = ctx_builder.build("avr8:LE:16:default").unwrap();
+        let code = sleigh.get_space_info(sleigh.get_code_space_idx()).unwrap();
+        assert_eq!(code.name, "code");
+        assert_eq!(code.word_size_bytes, 2);
+        assert!(matches!(code.endianness, SleighEndianness::Little));
+        let x86 = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let x86_code = x86.get_space_info(x86.get_code_space_idx()).unwrap();
+        assert_eq!(x86_code.word_size_bytes, 1);
+    }
+
+    #[test]
+    fn get_userops() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let userops: Vec<(usize, &str)> = sleigh.userops().collect();
+        assert!(!userops.is_empty());
+        let idx = sleigh.userop_index("segment").unwrap();
+        assert_eq!(userops[idx], (idx, "segment"));
+        assert_eq!(sleigh.get_userop_name(idx), Some("segment"));
+        assert_eq!(sleigh.userop_index("fake"), None);
+    }
+
+    #[test]
+    fn set_invalid_context_register() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let mut sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        assert!(sleigh.set_context_register("longMode", 0).is_ok());
+        assert!(matches!(
+            sleigh.set_context_register("fake", 0),
+            Err(JingleSleighError::InvalidContextVariable(_))
+        ));
+    }
 }
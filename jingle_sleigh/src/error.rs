@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// An error (usually from across the FFI boundary) in something involving sleigh
@@ -7,6 +8,11 @@ pub enum JingleSleighError {
     /// Probably indicates that the path to the language specification was wrong
     #[error("Unable to parse sleigh language!")]
     LanguageSpecRead,
+    /// A language definition referenced a `.sla` file that does not exist. Ghidra only ships
+    /// the `.slaspec` sources; the compiled `.sla` files are produced by running the sleigh
+    /// compiler (e.g. through a Ghidra build or the first launch of a Ghidra installation)
+    #[error("sleigh specification {0} has not been compiled; compile it with the sleigh compiler")]
+    SlaNotCompiled(PathBuf),
     /// A language specification existed, but was unable to be parsed
     #[error("failed to parse sleigh language definition")]
     LanguageSpecParse(#[from] serde_xml_rs::Error),
@@ -19,6 +25,9 @@ pub enum JingleSleighError {
     /// Unable to load the provided binary image for sleigh
     #[error("Something went wrong putting bytes into sleigh")]
     ImageLoadError,
+    /// An image was loaded whose architecture does not correspond to a known sleigh language
+    #[error("Unable to determine a sleigh language for this image")]
+    UnknownImageArchitecture,
     /// Attempted to initialize sleigh with an empty image
     #[error("You didn't provide any bytes to sleigh")]
     NoImageProvided,
@@ -29,6 +38,12 @@ pub enum JingleSleighError {
     /// A [`VarNode`](crate::VarNode) was constructed referencing a non-existent space
     #[error("A varnode was constructed referencing a non-existent space")]
     InvalidSpaceName,
+    /// A register was looked up by a name that the architecture does not define
+    #[error("A register was referenced by a name that does not exist")]
+    InvalidRegisterName,
+    /// A context variable was set by a name that the architecture does not define
+    #[error("{0} is not a context variable of this architecture")]
+    InvalidContextVariable(String),
     /// Attempted to construct an [Instruction](crate::Instruction) from an empty slice of instructions
     #[error("Attempted to construct an instruction from an empty slice of instructions")]
     EmptyInstruction,
@@ -36,6 +51,20 @@ pub enum JingleSleighError {
     SleighCompilerMutexError,
 }
 
+/// The reason an instruction could not be read from a loaded image
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes at the requested address do not encode an instruction
+    #[error("Sleigh was unable to decode an instruction")]
+    UnknownInstruction,
+    /// The requested address is not covered by the image
+    #[error("The requested address is outside of the image")]
+    OutOfBounds,
+    /// The image ends partway through the instruction at the requested address
+    #[error("The image does not contain all of the bytes of this instruction")]
+    IncompleteBytes,
+}
+
 impl From<JingleSleighError> for std::fmt::Error {
     fn from(_value: JingleSleighError) -> Self {
         std::fmt::Error
@@ -40,8 +40,12 @@ pub(crate) mod bridge {
             value: u32,
         ) -> Result<()>;
 
+        pub(crate) fn get_context_default(&self, name: &str) -> Result<u32>;
+
         pub(crate) fn get_one_instruction(&self, offset: u64) -> Result<InstructionFFI>;
 
+        pub(crate) fn get_instruction_length(&self, offset: u64) -> Result<usize>;
+
         pub(crate) fn getSpaceByIndex(&self, idx: i32) -> SharedPtr<AddrSpaceHandle>;
         pub(crate) fn getNumSpaces(&self) -> i32;
 
@@ -50,6 +54,8 @@ pub(crate) mod bridge {
 
         pub(crate) fn getRegisters(&self) -> Vec<RegisterInfoFFI>;
 
+        pub(crate) fn getUserOpNames(&self) -> Vec<String>;
+
         pub(crate) fn setImage(self: Pin<&mut ContextFFI>, img: &ImageFFI) -> Result<()>;
     }
 
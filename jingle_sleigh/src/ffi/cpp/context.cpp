@@ -34,6 +34,10 @@ void ContextFFI::set_initial_context(rust::Str name, uint32_t val) {
   sleigh.setContextDefault(name.operator std::string(), val);
 }
 
+uint32_t ContextFFI::get_context_default(rust::Str name) const {
+  return c_db.getDefaultValue(name.operator std::string());
+}
+
 std::shared_ptr<AddrSpaceHandle>
 ContextFFI::getSpaceByIndex(ghidra::int4 idx) const {
   return std::make_shared<AddrSpaceHandle>(sleigh.getSpace(idx));
@@ -67,6 +71,17 @@ rust::Vec<RegisterInfoFFI> ContextFFI::getRegisters() const {
   return v;
 }
 
+rust::Vec<rust::String> ContextFFI::getUserOpNames() const {
+  std::vector<std::string> names;
+  rust::Vec<rust::String> v;
+  sleigh.getUserOpNames(names);
+  v.reserve(names.size());
+  for (auto const &name : names) {
+    v.emplace_back(name);
+  }
+  return v;
+}
+
 void ContextFFI::setImage(ImageFFI const &img) {
   sleigh.reset(new RustLoadImage(img), &c_db);
   ghidra::DocumentStorage documentStorage = ghidra::DocumentStorage();
@@ -78,8 +93,10 @@ InstructionFFI ContextFFI::get_one_instruction(uint64_t offset) const {
   JingleAssemblyEmitter assembly;
   ghidra::Address a = ghidra::Address(sleigh.getDefaultCodeSpace(), offset);
   sleigh.printAssembly(assembly, a);
-  sleigh.oneInstruction(pcode, a);
-  size_t length = sleigh.instructionLength(a);
+  // For instructions with delay slots, sleigh emits the p-code of the delay slot
+  // instructions along with the instruction itself, and the returned length
+  // covers them as well.
+  size_t length = sleigh.oneInstruction(pcode, a);
   InstructionFFI i;
   Disassembly d;
   i.ops = std::move(pcode.ops);
@@ -91,6 +108,11 @@ InstructionFFI ContextFFI::get_one_instruction(uint64_t offset) const {
   return i;
 }
 
+size_t ContextFFI::get_instruction_length(uint64_t offset) const {
+  ghidra::Address a = ghidra::Address(sleigh.getDefaultCodeSpace(), offset);
+  return sleigh.instructionLength(a);
+}
+
 std::unique_ptr<ContextFFI> makeContext(rust::Str slaPath) {
   return std::make_unique<ContextFFI>(slaPath);
 }
@@ -22,10 +22,14 @@ public:
 
     void set_initial_context(rust::Str name, uint32_t val);
 
+    uint32_t get_context_default(rust::Str name) const;
+
     void setImage(ImageFFI const&img);
 
     InstructionFFI get_one_instruction(uint64_t offset) const;
 
+    size_t get_instruction_length(uint64_t offset) const;
+
     [[nodiscard]] std::shared_ptr<AddrSpaceHandle> getSpaceByIndex(ghidra::int4 idx) const;
 
     int getNumSpaces() const;
@@ -35,6 +39,8 @@ public:
     rust::Str getRegisterName(VarnodeInfoFFI name) const;
 
     rust::Vec<RegisterInfoFFI> getRegisters() const;
+
+    rust::Vec<rust::String> getUserOpNames() const;
 };
 
 RegisterInfoFFI collectRegInfo(std::tuple<ghidra::VarnodeData*, std::string> el);
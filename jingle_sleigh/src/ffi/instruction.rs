@@ -3,7 +3,11 @@ use std::fmt::{Display, Formatter};
 
 impl Display for Disassembly {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.mnemonic, self.args)
+        if self.args.is_empty() {
+            write!(f, "{}", self.mnemonic)
+        } else {
+            write!(f, "{} {}", self.mnemonic, self.args)
+        }
     }
 }
 
@@ -1,9 +1,10 @@
 use crate::error::JingleSleighError;
 pub use crate::ffi::instruction::bridge::Disassembly;
 use crate::ffi::instruction::bridge::InstructionFFI;
+use crate::pcode::display::GhidraInstructionDisplay;
 use crate::pcode::PcodeOperation;
 use crate::JingleSleighError::EmptyInstruction;
-use crate::OpCode;
+use crate::{OpCode, SpaceManager};
 use serde::{Deserialize, Serialize};
 
 /// A rust representation of a SLEIGH assembly instruction
@@ -13,7 +14,9 @@ pub struct Instruction {
     /// The PCODE semantics of this instruction
     /// todo: this should someday be a graph instead of a vec
     pub ops: Vec<PcodeOperation>,
-    /// The number of bytes taken up by the encoding of this assembly instruction
+    /// The number of bytes taken up by the encoding of this assembly instruction. For
+    /// instructions with delay slots, this includes the delay slot instructions, whose
+    /// semantics `SLEIGH` includes in [ops](Self::ops).
     pub length: usize,
     /// The address this instruction was read from
     pub address: u64,
@@ -31,6 +34,22 @@ impl Instruction {
         self.ops.iter().any(|o| o.terminates_block())
     }
 
+    /// Render this instruction as a line of a disassembly listing, e.g. `0x401000: MOV RAX,RBX`
+    pub fn display_with_address(&self) -> String {
+        format!("{:#x}: {}", self.address, self.disassembly)
+    }
+
+    /// Display the `p-code` of this instruction in the format of Ghidra's p-code listing
+    pub fn display_ghidra<'a, T: SpaceManager>(
+        &'a self,
+        ctx: &'a T,
+    ) -> GhidraInstructionDisplay<'a, T> {
+        GhidraInstructionDisplay {
+            instruction: self,
+            ctx,
+        }
+    }
+
     pub fn has_syscall(&self) -> bool {
         self.ops
             .iter()
@@ -75,3 +94,36 @@ impl TryFrom<&[Instruction]> for Instruction {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Disassembly, Instruction};
+
+    #[test]
+    fn test_disassembly_display() {
+        let with_args = Disassembly {
+            mnemonic: "MOV".to_string(),
+            args: "RAX,RBX".to_string(),
+        };
+        assert_eq!(with_args.to_string(), "MOV RAX,RBX");
+        let no_args = Disassembly {
+            mnemonic: "RET".to_string(),
+            args: "".to_string(),
+        };
+        assert_eq!(no_args.to_string(), "RET");
+    }
+
+    #[test]
+    fn test_display_with_address() {
+        let instr = Instruction {
+            disassembly: Disassembly {
+                mnemonic: "MOV".to_string(),
+                args: "RAX,RBX".to_string(),
+            },
+            ops: vec![],
+            length: 3,
+            address: 0x401000,
+        };
+        assert_eq!(instr.display_with_address(), "0x401000: MOV RAX,RBX");
+    }
+}
@@ -7,11 +7,11 @@ pub(crate) mod pcode;
 pub(crate) mod space;
 pub(crate) mod varnode;
 
-pub use error::JingleSleighError;
+pub use error::{DecodeError, JingleSleighError};
 pub use ffi::addrspace::bridge::SpaceType;
 pub use instruction::*;
 pub use pcode::*;
-pub use space::{RegisterManager, SleighEndianness, SpaceInfo, SpaceManager};
+pub use space::{RegisterManager, SleighEndianness, SpaceInfo, SpaceManager, UserOpManager};
 pub use varnode::display::*;
 pub use varnode::{create_varnode, GeneralizedVarNode, IndirectVarNode, VarNode};
 
@@ -0,0 +1,125 @@
+use crate::error::JingleSleighError;
+use crate::pcode::PcodeOperation;
+use crate::{GeneralizedVarNode, OpCode, RegisterManager, SpaceType, UserOpManager, VarNode};
+
+/// A [VarNode] with its architecture-specific details resolved away: registers are referred to
+/// by name, and other locations by the type of space they live in rather than its index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CanonicalVarNode {
+    Register(String),
+    Raw {
+        space_type: SpaceType,
+        offset: u64,
+        size: usize,
+    },
+    Indirect {
+        pointer_space_type: SpaceType,
+        pointer_location: Box<CanonicalVarNode>,
+        access_size_bytes: usize,
+    },
+    /// The index input of a `CALLOTHER`, resolved to the name of the user-defined operation
+    UserOp(String),
+}
+
+/// A [PcodeOperation] in a form that can be compared across architectures. Two operations
+/// canonicalize equal if they apply the same opcode to the same registers, constants and
+/// (types of) spaces, regardless of how each architecture numbers its spaces and userops.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CanonicalOp {
+    pub opcode: OpCode,
+    pub inputs: Vec<CanonicalVarNode>,
+    pub output: Option<CanonicalVarNode>,
+}
+
+impl CanonicalVarNode {
+    fn from_varnode<T: RegisterManager>(vn: &VarNode, info: &T) -> Result<Self, JingleSleighError> {
+        if let Some(name) = info.get_register_name(vn) {
+            return Ok(CanonicalVarNode::Register(name.to_string()));
+        }
+        let space = info
+            .get_space_info(vn.space_index)
+            .ok_or(JingleSleighError::InvalidSpaceName)?;
+        Ok(CanonicalVarNode::Raw {
+            space_type: space._type,
+            offset: vn.offset,
+            size: vn.size,
+        })
+    }
+
+    fn from_generalized<T: RegisterManager>(
+        vn: &GeneralizedVarNode,
+        info: &T,
+    ) -> Result<Self, JingleSleighError> {
+        match vn {
+            GeneralizedVarNode::Direct(d) => Self::from_varnode(d, info),
+            GeneralizedVarNode::Indirect(i) => {
+                let space = info
+                    .get_space_info(i.pointer_space_index)
+                    .ok_or(JingleSleighError::InvalidSpaceName)?;
+                Ok(CanonicalVarNode::Indirect {
+                    pointer_space_type: space._type,
+                    pointer_location: Box::new(Self::from_varnode(&i.pointer_location, info)?),
+                    access_size_bytes: i.access_size_bytes,
+                })
+            }
+        }
+    }
+}
+
+impl PcodeOperation {
+    /// Produce the [CanonicalOp] form of this operation, resolving registers, spaces and userops
+    /// using `info`
+    pub fn canonical<T: RegisterManager + UserOpManager>(
+        &self,
+        info: &T,
+    ) -> Result<CanonicalOp, JingleSleighError> {
+        let mut inputs = self
+            .inputs()
+            .iter()
+            .map(|i| CanonicalVarNode::from_generalized(i, info))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let (Some(name), Some(first)) = (self.callother_name(info), inputs.first_mut()) {
+            *first = CanonicalVarNode::UserOp(name.to_string());
+        }
+        let output = self
+            .output()
+            .map(|o| CanonicalVarNode::from_generalized(&o, info))
+            .transpose()?;
+        Ok(CanonicalOp {
+            opcode: self.opcode(),
+            inputs,
+            output,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::SleighContextBuilder;
+    use crate::pcode::canonical::CanonicalVarNode;
+    use crate::{varnode, PcodeOperation, RegisterManager, SpaceManager};
+
+    #[test]
+    fn test_canonical_across_architectures() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let canonical = |arch: &str, reg: &str| {
+            let sleigh = ctx_builder.build(arch).unwrap();
+            let eax = sleigh.get_register(reg).unwrap();
+            let op = PcodeOperation::IntAdd {
+                input0: eax.clone(),
+                input1: varnode!(&sleigh, #1:4).unwrap(),
+                output: eax,
+            };
+            op.canonical(&sleigh).unwrap()
+        };
+        let x86_64 = canonical("x86:LE:64:default", "EAX");
+        let x86_32 = canonical("x86:LE:32:default", "EAX");
+        assert_eq!(x86_64, x86_32);
+        assert_eq!(
+            x86_64.output,
+            Some(CanonicalVarNode::Register("EAX".to_string()))
+        );
+        assert_ne!(x86_64, canonical("x86:LE:32:default", "EBX"));
+    }
+}
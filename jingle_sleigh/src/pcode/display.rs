@@ -1,5 +1,5 @@
 use crate::pcode::PcodeOperation;
-use crate::RegisterManager;
+use crate::{GeneralizedVarNode, Instruction, RegisterManager, SpaceManager, VarNode};
 use std::fmt::{Display, Formatter};
 
 pub struct PcodeOperationDisplay<'a, T: RegisterManager> {
@@ -27,9 +27,116 @@ where
     }
 }
 
+/// Displays a [`PcodeOperation`] the way Ghidra's p-code listing does, e.g.
+/// `(unique, 0x3b80, 8) INT_ADD (register, 0x20, 8) , (const, 0x8, 8)`.
+///
+/// Indirect varnodes are shown as their pointer location. Ghidra's listing also shows the
+/// space-id constant that is the first input of `LOAD` and `STORE`; `jingle` does not retain
+/// that varnode, so it is omitted.
+pub struct GhidraPcodeOperationDisplay<'a, T: SpaceManager> {
+    pub(crate) op: PcodeOperation,
+    pub(crate) ctx: &'a T,
+}
+
+impl<T: SpaceManager> GhidraPcodeOperationDisplay<'_, T> {
+    fn fmt_varnode(&self, vn: &VarNode) -> Result<String, std::fmt::Error> {
+        let space = self
+            .ctx
+            .get_space_info(vn.space_index)
+            .ok_or(std::fmt::Error)?;
+        Ok(format!("({}, {:#x}, {})", space.name, vn.offset, vn.size))
+    }
+
+    fn fmt_generalized(&self, vn: &GeneralizedVarNode) -> Result<String, std::fmt::Error> {
+        match vn {
+            GeneralizedVarNode::Direct(d) => self.fmt_varnode(d),
+            GeneralizedVarNode::Indirect(i) => self.fmt_varnode(&i.pointer_location),
+        }
+    }
+}
+
+impl<T: SpaceManager> Display for GhidraPcodeOperationDisplay<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut args: Vec<String> = vec![];
+        match self.op.output() {
+            // A STORE "writes" through its pointer, which Ghidra lists as an input
+            Some(o @ GeneralizedVarNode::Indirect(_)) => args.push(self.fmt_generalized(&o)?),
+            Some(o) => write!(f, "{} ", self.fmt_generalized(&o)?)?,
+            None => {}
+        }
+        write!(f, "{}", self.op.opcode())?;
+        for x in self.op.inputs() {
+            args.push(self.fmt_generalized(&x)?);
+        }
+        if !args.is_empty() {
+            write!(f, " {}", args.join(" , "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays the p-code of an [`Instruction`] as a Ghidra-style listing, one operation per line.
+/// See [`GhidraPcodeOperationDisplay`].
+pub struct GhidraInstructionDisplay<'a, T: SpaceManager> {
+    pub(crate) instruction: &'a Instruction,
+    pub(crate) ctx: &'a T,
+}
+
+impl<T: SpaceManager> Display for GhidraInstructionDisplay<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let lines: Vec<String> = self
+            .instruction
+            .ops
+            .iter()
+            .map(|op| op.display_ghidra(self.ctx).to_string())
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
 impl Display for crate::ffi::opcode::bridge::OpCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let d = format!("{:?}", self);
         write!(f, "{}", &d[5..])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::context::SleighContextBuilder;
+    use crate::tests::SLEIGH_ARCH;
+    use crate::{varnode, PcodeOperation, SpaceManager};
+
+    #[test]
+    fn test_ghidra_display() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let op = PcodeOperation::IntAdd {
+            output: varnode!(&sleigh, "unique"[0x3b80]:8).unwrap(),
+            input0: varnode!(&sleigh, "register"[0x20]:8).unwrap(),
+            input1: varnode!(&sleigh, #8:8).unwrap(),
+        };
+        assert_eq!(
+            op.display_ghidra(&sleigh).to_string(),
+            "(unique, 0x3b80, 8) INT_ADD (register, 0x20, 8) , (const, 0x8, 8)"
+        );
+    }
+
+    #[test]
+    fn test_ghidra_instruction_display() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // MOV EAX, 0
+        let img: [u8; 5] = [0xb8, 0x00, 0x00, 0x00, 0x00];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let instr = loaded.instruction_at(0).unwrap();
+        let listing = instr.display_ghidra(&loaded).to_string();
+        assert_eq!(
+            listing.lines().next(),
+            Some("(register, 0x0, 4) COPY (const, 0x0, 4)")
+        );
+        assert_eq!(listing.lines().count(), instr.ops.len());
+    }
+}
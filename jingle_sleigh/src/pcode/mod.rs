@@ -1,4 +1,5 @@
 pub mod branch;
+pub mod canonical;
 pub mod display;
 
 use crate::pcode::PcodeOperation::{
@@ -16,10 +17,12 @@ use crate::pcode::PcodeOperation::{
 use crate::error::JingleSleighError;
 use crate::ffi::instruction::bridge::RawPcodeOp;
 pub use crate::ffi::opcode::OpCode;
-use crate::pcode::display::PcodeOperationDisplay;
+pub use crate::pcode::canonical::{CanonicalOp, CanonicalVarNode};
+use crate::pcode::display::{GhidraPcodeOperationDisplay, PcodeOperationDisplay};
 use crate::varnode::{IndirectVarNode, VarNode};
-use crate::{GeneralizedVarNode, RegisterManager};
+use crate::{GeneralizedVarNode, RegisterManager, SpaceManager, UserOpManager};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
@@ -387,6 +390,21 @@ impl PcodeOperation {
         )
     }
 
+    /// For [`CallOther`] operations, the index of the user-defined operation being invoked. This is
+    /// encoded by `SLEIGH` as the offset of the first (constant) input.
+    pub fn callother_index(&self) -> Option<u64> {
+        match self {
+            CallOther { inputs, .. } => inputs.first().map(|vn| vn.offset),
+            _ => None,
+        }
+    }
+
+    /// For [`CallOther`] operations, the name of the user-defined operation being invoked.
+    pub fn callother_name<'a, T: UserOpManager>(&self, info: &'a T) -> Option<&'a str> {
+        self.callother_index()
+            .and_then(|idx| info.get_userop_name(idx as usize))
+    }
+
     pub fn display<'a, T: RegisterManager>(
         &self,
         ctx: &'a T,
@@ -397,6 +415,17 @@ impl PcodeOperation {
         })
     }
 
+    /// Display this operation in the format of Ghidra's p-code listing
+    pub fn display_ghidra<'a, T: SpaceManager>(
+        &self,
+        ctx: &'a T,
+    ) -> GhidraPcodeOperationDisplay<'a, T> {
+        GhidraPcodeOperationDisplay {
+            op: self.clone(),
+            ctx,
+        }
+    }
+
     pub fn inputs(&self) -> Vec<GeneralizedVarNode> {
         match self {
             Copy { input, .. } => {
@@ -691,6 +720,299 @@ impl PcodeOperation {
             LzCount { output, .. } => Some(GeneralizedVarNode::from(output)),
         }
     }
+
+    /// Iterate over every varnode referenced by this operation: its inputs, followed by its
+    /// output if it has one.
+    pub fn varnodes(&self) -> impl Iterator<Item = GeneralizedVarNode> {
+        self.inputs().into_iter().chain(self.output())
+    }
+
+    /// Produce a copy of this operation with every direct varnode replaced by the result of
+    /// calling `f` on it. Indirect varnodes keep their pointer space and access size, but have
+    /// their pointer location mapped. Varnodes are visited inputs first, then the output.
+    pub fn map_varnodes<F: FnMut(&VarNode) -> VarNode>(&self, mut f: F) -> PcodeOperation {
+        let mut op = self.clone();
+        for vn in op.varnodes_mut() {
+            *vn = f(vn);
+        }
+        op
+    }
+
+    /// Produce a copy of this operation with every direct varnode (and every pointer location of
+    /// an indirect varnode) found in `map` replaced by its mapped value. Varnodes absent from
+    /// `map` are left alone.
+    pub fn substitute(&self, map: &HashMap<VarNode, VarNode>) -> PcodeOperation {
+        self.map_varnodes(|vn| map.get(vn).unwrap_or(vn).clone())
+    }
+
+    /// Mutable references to each direct varnode of this operation (including the pointer
+    /// locations of indirect varnodes), inputs first
+    fn varnodes_mut(&mut self) -> Vec<&mut VarNode> {
+        match self {
+            Copy { input, output }
+            | IntSExt { input, output }
+            | IntZExt { input, output }
+            | Int2Comp { input, output }
+            | IntNegate { input, output }
+            | BoolNegate { input, output }
+            | FloatNaN { input, output }
+            | FloatNeg { input, output }
+            | FloatAbs { input, output }
+            | FloatSqrt { input, output }
+            | FloatIntToFloat { input, output }
+            | FloatFloatToFloat { input, output }
+            | FloatTrunc { input, output }
+            | FloatCeil { input, output }
+            | FloatFloor { input, output }
+            | FloatRound { input, output }
+            | Cast { input, output }
+            | PopCount { input, output }
+            | LzCount { input, output } => vec![input, output],
+            IntEqual {
+                output,
+                input0,
+                input1,
+            }
+            | IntNotEqual {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedLess {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedLessEqual {
+                output,
+                input0,
+                input1,
+            }
+            | IntLess {
+                output,
+                input0,
+                input1,
+            }
+            | IntLessEqual {
+                output,
+                input0,
+                input1,
+            }
+            | IntAdd {
+                output,
+                input0,
+                input1,
+            }
+            | IntSub {
+                output,
+                input0,
+                input1,
+            }
+            | IntCarry {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedCarry {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedBorrow {
+                output,
+                input0,
+                input1,
+            }
+            | IntXor {
+                output,
+                input0,
+                input1,
+            }
+            | IntAnd {
+                output,
+                input0,
+                input1,
+            }
+            | IntOr {
+                output,
+                input0,
+                input1,
+            }
+            | IntLeftShift {
+                output,
+                input0,
+                input1,
+            }
+            | IntRightShift {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedRightShift {
+                output,
+                input0,
+                input1,
+            }
+            | IntMult {
+                output,
+                input0,
+                input1,
+            }
+            | IntDiv {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedDiv {
+                output,
+                input0,
+                input1,
+            }
+            | IntRem {
+                output,
+                input0,
+                input1,
+            }
+            | IntSignedRem {
+                output,
+                input0,
+                input1,
+            }
+            | BoolXor {
+                output,
+                input0,
+                input1,
+            }
+            | BoolAnd {
+                output,
+                input0,
+                input1,
+            }
+            | BoolOr {
+                output,
+                input0,
+                input1,
+            }
+            | FloatEqual {
+                output,
+                input0,
+                input1,
+            }
+            | FloatNotEqual {
+                output,
+                input0,
+                input1,
+            }
+            | FloatLess {
+                output,
+                input0,
+                input1,
+            }
+            | FloatLessEqual {
+                output,
+                input0,
+                input1,
+            }
+            | FloatAdd {
+                output,
+                input0,
+                input1,
+            }
+            | FloatDiv {
+                output,
+                input0,
+                input1,
+            }
+            | FloatMult {
+                output,
+                input0,
+                input1,
+            }
+            | FloatSub {
+                output,
+                input0,
+                input1,
+            }
+            | Indirect {
+                output,
+                input0,
+                input1,
+            }
+            | Piece {
+                output,
+                input0,
+                input1,
+            }
+            | SubPiece {
+                output,
+                input0,
+                input1,
+            }
+            | PtrSub {
+                output,
+                input0,
+                input1,
+            } => vec![input0, input1, output],
+            Load { input, output } => vec![&mut input.pointer_location, output],
+            Store { output, input } => vec![input, &mut output.pointer_location],
+            Branch { input } | Call { input } => vec![input],
+            CBranch { input0, input1 } => vec![input0, input1],
+            BranchInd { input } | CallInd { input } | Return { input } => {
+                vec![&mut input.pointer_location]
+            }
+            CallOther { output, inputs } => inputs.iter_mut().chain(output.as_mut()).collect(),
+            MultiEqual {
+                input0,
+                input1,
+                inputs,
+                output,
+            }
+            | CPoolRef {
+                input0,
+                input1,
+                inputs,
+                output,
+            } => [input0, input1]
+                .into_iter()
+                .chain(inputs.iter_mut())
+                .chain([output])
+                .collect(),
+            PtrAdd {
+                output,
+                input0,
+                input1,
+                input2,
+            }
+            | SegmentOp {
+                output,
+                input0,
+                input1,
+                input2,
+            } => vec![input0, input1, input2, output],
+            New {
+                output,
+                input,
+                size,
+            } => [input]
+                .into_iter()
+                .chain(size.as_mut())
+                .chain([output])
+                .collect(),
+            Insert {
+                output,
+                input0,
+                input1,
+                position,
+                size,
+            } => vec![input0, input1, position, size, output],
+            Extract {
+                output,
+                input0,
+                position,
+                size,
+            } => vec![input0, position, size, output],
+        }
+    }
 }
 
 impl From<RawPcodeOp> for PcodeOperation {
@@ -979,3 +1301,115 @@ impl From<&PcodeOperation> for OpCode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::context::SleighContextBuilder;
+    use crate::tests::SLEIGH_ARCH;
+    use crate::{
+        varnode, GeneralizedVarNode, IndirectVarNode, PcodeOperation, RegisterManager, SpaceManager,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_map_varnodes() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let rax = sleigh.get_register("RAX").unwrap();
+        let rbx = sleigh.get_register("RBX").unwrap();
+        let one = varnode!(&sleigh, #1:8).unwrap();
+        let op = PcodeOperation::IntAdd {
+            output: rax.clone(),
+            input0: rax.clone(),
+            input1: one.clone(),
+        };
+        let varnodes: Vec<GeneralizedVarNode> = op.varnodes().collect();
+        assert_eq!(varnodes, vec![(&rax).into(), (&one).into(), (&rax).into()]);
+        let mapped = op.map_varnodes(|vn| if vn == &one { rbx.clone() } else { vn.clone() });
+        assert_eq!(
+            mapped,
+            PcodeOperation::IntAdd {
+                output: rax.clone(),
+                input0: rax.clone(),
+                input1: rbx.clone(),
+            }
+        );
+        let store = PcodeOperation::Store {
+            output: IndirectVarNode {
+                pointer_space_index: sleigh.get_code_space_idx(),
+                pointer_location: rax.clone(),
+                access_size_bytes: 8,
+            },
+            input: one.clone(),
+        };
+        let mapped = store.map_varnodes(|vn| if vn == &rax { rbx.clone() } else { vn.clone() });
+        match mapped {
+            PcodeOperation::Store { output, input } => {
+                assert_eq!(output.pointer_location, rbx);
+                assert_eq!(output.pointer_space_index, sleigh.get_code_space_idx());
+                assert_eq!(output.access_size_bytes, 8);
+                assert_eq!(input, one);
+            }
+            _ => panic!("mapping changed the opcode"),
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let rax = sleigh.get_register("RAX").unwrap();
+        let rbx = sleigh.get_register("RBX").unwrap();
+        let rcx = sleigh.get_register("RCX").unwrap();
+        let map = HashMap::from([(rax.clone(), rbx.clone())]);
+        let op = PcodeOperation::IntAdd {
+            output: rax.clone(),
+            input0: rax.clone(),
+            input1: rcx.clone(),
+        };
+        assert_eq!(
+            op.substitute(&map),
+            PcodeOperation::IntAdd {
+                output: rbx.clone(),
+                input0: rbx.clone(),
+                input1: rcx.clone(),
+            }
+        );
+        let callother = PcodeOperation::CallOther {
+            output: Some(rcx.clone()),
+            inputs: vec![varnode!(&sleigh, #0:4).unwrap(), rax.clone()],
+        };
+        assert_eq!(
+            callother.substitute(&map),
+            PcodeOperation::CallOther {
+                output: Some(rcx.clone()),
+                inputs: vec![varnode!(&sleigh, #0:4).unwrap(), rbx.clone()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_callother_name() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        // RDTSC
+        let img: [u8; 2] = [0x0f, 0x31];
+        let loaded = sleigh.initialize_with_image(img.as_slice()).unwrap();
+        let instr = loaded.instruction_at(0).unwrap();
+        let callother = instr
+            .ops
+            .iter()
+            .find(|op| matches!(op, PcodeOperation::CallOther { .. }))
+            .unwrap();
+        assert!(callother.callother_index().is_some());
+        assert_eq!(callother.callother_name(&loaded), Some("rdtsc"));
+        assert!(instr
+            .ops
+            .iter()
+            .filter(|op| !matches!(op, PcodeOperation::CallOther { .. }))
+            .all(|op| op.callother_index().is_none()));
+    }
+}
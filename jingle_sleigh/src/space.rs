@@ -99,6 +99,35 @@ pub trait SpaceManager {
     /// Returns the index that `SLEIGH` claims is the "main" space in which instructions reside
     fn get_code_space_idx(&self) -> usize;
 
+    /// Returns the index of the `unique` space: the `SLEIGH`-internal scratch space used for
+    /// temporaries within a single instruction.
+    fn get_unique_space_idx(&self) -> Option<usize> {
+        self.get_all_space_info()
+            .iter()
+            .position(|s| s._type == SpaceType::IPTR_INTERNAL)
+    }
+
+    /// Whether the space with the given index is the `const` space, in which the offset of a
+    /// [`VarNode`] is its value
+    fn is_const_space(&self, idx: usize) -> bool {
+        self.get_space_info(idx)
+            .is_some_and(|s| s._type == SpaceType::IPTR_CONSTANT)
+    }
+
+    /// A helper function to generate a [`VarNode`] in the `const` space holding `value`
+    fn const_varnode(&self, value: u64, size: usize) -> Result<VarNode, JingleSleighError> {
+        let space_index = self
+            .get_all_space_info()
+            .iter()
+            .position(|s| s._type == SpaceType::IPTR_CONSTANT)
+            .ok_or(JingleSleighError::InvalidSpaceName)?;
+        Ok(VarNode {
+            space_index,
+            size,
+            offset: value,
+        })
+    }
+
     /// A helper function to generate a [`VarNode`] using the name of a space
     fn varnode(&self, name: &str, offset: u64, size: usize) -> Result<VarNode, JingleSleighError> {
         for (space_index, space) in self.get_all_space_info().iter().enumerate() {
@@ -127,6 +156,32 @@ pub trait RegisterManager: SpaceManager {
     fn get_registers(&self) -> Vec<(VarNode, String)>;
 }
 
+/// This trait indicates that the implementing type knows the names of the user-defined operations
+/// declared by a `SLEIGH` specification. These are the targets of `CALLOTHER` operations, which
+/// refer to them by index through their first (constant) input.
+pub trait UserOpManager {
+    /// Get a listing of all user-defined operation names, ordered by index
+    fn get_userop_names(&self) -> &[String];
+
+    /// Given the index of a user-defined operation, get its name, if it exists
+    fn get_userop_name(&self, idx: usize) -> Option<&str> {
+        self.get_userop_names().get(idx).map(|s| s.as_str())
+    }
+
+    /// Iterate over all user-defined operations as index/name pairs
+    fn userops(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.get_userop_names()
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (idx, name.as_str()))
+    }
+
+    /// Given the name of a user-defined operation, get its index, if it exists
+    fn userop_index(&self, name: &str) -> Option<usize> {
+        self.get_userop_names().iter().position(|s| s == name)
+    }
+}
+
 /// `jingle` models traces of code using slices, so it is helpful to implement some of these
 /// traits on slices of types that implement those same traits.
 impl<T: SpaceManager> SpaceManager for &[T] {
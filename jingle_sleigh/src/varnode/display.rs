@@ -30,7 +30,7 @@ pub enum GeneralizedVarNodeDisplay {
 impl Display for RawVarNodeDisplay {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.space_info._type == SpaceType::IPTR_CONSTANT {
-            write!(f, "{:x}:{:x}", self.offset, self.size)
+            write!(f, "#{:#x}:{:x}", self.offset, self.size)
         } else {
             write!(
                 f,
@@ -75,3 +75,40 @@ impl Display for GeneralizedVarNodeDisplay {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::varnode::display::RawVarNodeDisplay;
+    use crate::{SleighEndianness, SpaceInfo, SpaceType};
+
+    fn space_info(name: &str, _type: SpaceType) -> SpaceInfo {
+        SpaceInfo {
+            name: name.to_string(),
+            index: 0,
+            index_size_bytes: 8,
+            word_size_bytes: 1,
+            _type,
+            endianness: SleighEndianness::Little,
+        }
+    }
+
+    #[test]
+    fn test_const_display() {
+        let vn = RawVarNodeDisplay {
+            offset: 5,
+            size: 1,
+            space_info: space_info("const", SpaceType::IPTR_CONSTANT),
+        };
+        assert_eq!(format!("{}", vn), "#0x5:1");
+    }
+
+    #[test]
+    fn test_raw_display() {
+        let vn = RawVarNodeDisplay {
+            offset: 0x10,
+            size: 4,
+            space_info: space_info("ram", SpaceType::IPTR_PROCESSOR),
+        };
+        assert_eq!(format!("{}", vn), "ram[10]:4");
+    }
+}
@@ -19,7 +19,8 @@ use std::ops::Range;
 /// and outputs of the instruction semantics.
 ///
 /// In `jingle`, we follow `SLEIGH`'s convention and display these as
-/// `<space>\[<offset>\]:<size>`. In the case of constants, we simplify this to `<offset>:<size>`.
+/// `<space>\[<offset>\]:<size>`. In the case of constants, we simplify this to `#<offset>:<size>`,
+/// matching the syntax accepted by the [`varnode!`](crate::varnode!) macro.
 /// For registers, we will (soon! (TM)) perform a register lookup and instead show the pretty
 /// architecture-defined register name.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
@@ -89,6 +90,10 @@ macro_rules! varnode {
     ($ctx:expr, $space:literal[$offset:expr]:$size:literal) => {
         $ctx.varnode($space, $offset, $size)
     };
+    ($ctx:expr, $reg:ident) => {
+        $ctx.get_register(stringify!($reg))
+            .ok_or($crate::JingleSleighError::InvalidRegisterName)
+    };
 }
 
 pub fn create_varnode<T: SpaceManager>(
@@ -201,7 +206,25 @@ impl From<&VarnodeInfoFFI> for VarNode {
 
 #[cfg(test)]
 mod tests {
-    use crate::VarNode;
+    use crate::context::SleighContextBuilder;
+    use crate::tests::SLEIGH_ARCH;
+    use crate::{JingleSleighError, RegisterManager, VarNode};
+
+    #[test]
+    fn test_register_macro() {
+        let ctx_builder =
+            SleighContextBuilder::load_ghidra_installation("/Applications/ghidra").unwrap();
+        let sleigh = ctx_builder.build(SLEIGH_ARCH).unwrap();
+        let rax = varnode!(&sleigh, RAX).unwrap();
+        assert_eq!(Some(rax.clone()), sleigh.get_register("RAX"));
+        assert_eq!(rax.size, 8);
+        let eax = varnode!(&sleigh, EAX).unwrap();
+        assert!(rax.covers(&eax));
+        assert!(matches!(
+            varnode!(&sleigh, NOT_A_REGISTER),
+            Err(JingleSleighError::InvalidRegisterName)
+        ));
+    }
 
     #[test]
     fn test_overlap() {